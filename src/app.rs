@@ -1,33 +1,70 @@
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crossterm::event::{self, Event, KeyEvent, KeyEventKind};
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Flex, Layout, Rect},
     style::{Modifier, Style, Stylize},
     symbols::border,
     text::{Line, Span},
     widgets::{
         block::{Position, Title},
-        Block, List, ListItem, ListState,
+        Block, Clear, List, ListItem, ListState, Paragraph, Wrap,
     },
     DefaultTerminal, Frame,
 };
 
-use crate::{config, error::AppError};
+use crate::{
+    backend::{Backend, CommandOutput},
+    colors::ColorSupport,
+    config::{self, ConfigWatcher},
+    data::{self, Location},
+    doctor::{self, Check, Verdict},
+    error::AppError,
+    favorites::{Favorite, Favorites},
+    fuzzy,
+    history::{Entry, History, Outcome},
+    keys::{Action, KeyChord},
+    settings::{Change, Choice, Settings, Toggle},
+    shutdown,
+    status::{self, Status},
+    task::{Operation, StatusPoller, Task},
+};
+
+const TICK_RATE: Duration = Duration::from_millis(100);
+const STATUS_INTERVAL: Duration = Duration::from_secs(5);
+const CONFIG_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const STATUS_PANE_WIDTH: u16 = 40;
+/// Pinned above every city list; connects to the country as a whole.
+const ANY_CITY: &str = "Any city (fastest)";
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 enum View {
     #[default]
     Countries,
     Cities,
     Connection,
+    Settings,
+    Technology,
+    Groups,
+    GroupCountries,
+    Favorites,
+    History,
+    Help,
+    /// The preflight checklist, shown on startup when a check fails.
+    Doctor,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 enum InputMode {
     #[default]
     Normal,
     Search,
+    /// Typing a server hostname to connect to.
+    Server,
 }
 
 impl Display for InputMode {
@@ -35,184 +72,593 @@ impl Display for InputMode {
         match self {
             InputMode::Normal => write!(f, "Normal"),
             InputMode::Search => write!(f, "Search"),
+            InputMode::Server => write!(f, "Server"),
+        }
+    }
+}
+
+/// A recoverable error, drawn as a modal over the current view.
+#[derive(Debug)]
+struct ErrorPopup {
+    title: String,
+    lines: Vec<String>,
+}
+
+impl From<AppError> for ErrorPopup {
+    fn from(error: AppError) -> Self {
+        let (title, mut lines) = match &error {
+            AppError::Command(output) => (format!("nordvpn failed ({})", output.status), vec![]),
+            AppError::NordVpn(kind, _) => (kind.to_string(), vec![]),
+            error => ("Error".to_string(), vec![error.to_string()]),
+        };
+        if let Some(remedy) = error.remedy() {
+            lines.push(remedy.to_string());
+        }
+
+        if let Some(output) = error.output() {
+            let mut printed = Vec::new();
+            for (name, text) in [("Output", &output.stdout), ("Errors", &output.stderr)] {
+                let text: Vec<String> = text
+                    .lines()
+                    .map(status::clean_line)
+                    .filter(|line| !line.is_empty())
+                    .collect();
+                if !text.is_empty() {
+                    printed.push(format!("{}:", name));
+                    printed.extend(text);
+                }
+            }
+            if printed.is_empty() {
+                printed.push("nordvpn printed nothing.".to_string());
+            }
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.extend(printed);
         }
+
+        Self { title, lines }
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct App {
     countries: Vec<String>,
     cities: Vec<String>,
+    groups: Vec<String>,
+    favorites: Favorites,
+    history: History,
 
     connection_output: Vec<String>,
-    connected: bool,
+    settings: Settings,
+    status: Status,
+    status_at: Instant,
+    poller: StatusPoller,
 
     search_string: String,
+    server_input: String,
+    server_error: Option<String>,
 
     view_mode: View,
     input_mode: InputMode,
     state: ListState,
 
-    awaiting_second_g: bool,
+    /// Keys typed so far towards a multi-key binding such as `g g`.
+    pending_keys: Vec<KeyChord>,
 
     country_index: usize,
     city_index: usize,
+    settings_index: usize,
+    technology_index: usize,
+    group_index: usize,
+    group_country_index: usize,
+    favorite_index: usize,
+    history_index: usize,
+    help_index: usize,
+    /// Results of the last preflight run.
+    checks: Vec<Check>,
+    doctor_index: usize,
 
     config: config::Config,
+    backend: Arc<dyn Backend>,
+    color_support: ColorSupport,
+    config_watcher: Option<ConfigWatcher>,
+    /// Why the last reload of the config failed; the previous one stays in use.
+    config_error: Option<String>,
+    /// A recoverable error shown over the current view until dismissed.
+    error: Option<ErrorPopup>,
+    task: Option<Task>,
 
     exit: bool,
 }
 
 impl App {
-    pub fn init(config: Option<String>) -> Result<Self, AppError> {
-        let config = config::Config::load(config.as_deref())?;
-
-        let output = std::process::Command::new("nordvpn")
-            .arg("countries")
-            .output()?;
+    pub fn init(
+        config: Option<String>,
+        backend: Arc<dyn Backend>,
+        color_support: ColorSupport,
+    ) -> Result<Self, AppError> {
+        let watcher = ConfigWatcher::new(config.as_deref(), CONFIG_CHECK_INTERVAL);
+        let checks = doctor::run(backend.as_ref(), config.as_deref());
+        // A broken config is only a warning in the checks, with the defaults in use.
+        let config = config::Config::load(config.as_deref()).unwrap_or_default();
+        let favorites = Favorites::load(Favorites::default_path())?;
+        let history = History::load(History::default_path())?;
 
-        let countries: Vec<String> = String::from_utf8(output.stdout)?
-            .split_whitespace()
-            .map(|s| s.to_string())
-            .collect();
-
-        let output = std::process::Command::new("nordvpn")
-            .arg("status")
-            .output()?;
+        let app = if doctor::passed(&checks) {
+            Self::new(config, backend)?
+        } else {
+            Self::empty(config, backend)
+        };
+        Ok(app
+            .with_color_support(color_support)
+            .with_config_watcher(watcher)
+            .with_favorites(favorites)
+            .with_history(history)
+            .with_checks(checks))
+    }
 
-        let connection_status = String::from_utf8(output.stdout)?.contains("Connected");
+    pub fn new(config: config::Config, backend: Arc<dyn Backend>) -> Result<Self, AppError> {
+        let mut app = Self::empty(config, backend);
+        app.load()?;
+        Ok(app)
+    }
 
+    /// An app with nothing fetched from the backend yet.
+    fn empty(config: config::Config, backend: Arc<dyn Backend>) -> Self {
         let mut state = ListState::default();
         state.select(Some(0));
 
-        Ok(Self {
-            countries,
+        Self {
+            countries: vec![],
             cities: vec![],
+            groups: vec![],
+            favorites: Favorites::default(),
+            history: History::default(),
             connection_output: vec![],
-            connected: connection_status,
+            settings: Settings::default(),
+            status: Status::default(),
+            status_at: Instant::now(),
+            poller: StatusPoller::new(STATUS_INTERVAL),
             search_string: String::default(),
+            server_input: String::default(),
+            server_error: None,
             country_index: 0,
             city_index: 0,
+            settings_index: 0,
+            technology_index: 0,
+            group_index: 0,
+            group_country_index: 0,
+            favorite_index: 0,
+            history_index: 0,
+            help_index: 0,
+            checks: vec![],
+            doctor_index: 0,
             input_mode: InputMode::default(),
             view_mode: View::default(),
-            awaiting_second_g: false,
+            pending_keys: Vec::new(),
             state,
             config,
+            backend,
+            color_support: ColorSupport::default(),
+            config_watcher: None,
+            config_error: None,
+            error: None,
+            task: None,
             exit: false,
-        })
+        }
+    }
+
+    /// Fetches what the lists and the status pane show.
+    fn load(&mut self) -> Result<(), AppError> {
+        self.countries = self.backend.countries()?;
+        self.status = self.backend.status()?;
+        self.settings = self.backend.settings()?;
+        Ok(())
+    }
+
+    /// Opens the checklist when one of `checks` failed.
+    pub fn with_checks(mut self, checks: Vec<Check>) -> Self {
+        if !doctor::passed(&checks) {
+            self.view_mode = View::Doctor;
+        }
+        self.checks = checks;
+        self
+    }
+
+    /// Uses `favorites`, landing on the favorites view when there are any.
+    pub fn with_favorites(mut self, favorites: Favorites) -> Self {
+        self.favorites = favorites;
+        if !self.favorites.is_empty() {
+            self.view_mode = View::Favorites;
+        }
+        self
+    }
+
+    /// Downgrades the configured colors to what the terminal supports.
+    pub fn with_color_support(mut self, color_support: ColorSupport) -> Self {
+        self.color_support = color_support;
+        self.config.colors = self.config.colors.downgrade(color_support);
+        self
+    }
+
+    pub fn with_config_watcher(mut self, watcher: ConfigWatcher) -> Self {
+        self.config_watcher = Some(watcher);
+        self
+    }
+
+    pub fn with_history(mut self, history: History) -> Self {
+        self.history = history;
+        self
     }
 
     pub fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), AppError> {
-        while !self.exit {
+        while !self.exit && shutdown::received().is_none() {
             terminal.draw(|frame| self.draw(frame))?;
+            let (view, mode) = (self.view_mode, self.input_mode);
             self.handle_events()?;
+            if let Err(e) = self.tick() {
+                self.show_error(e);
+            }
+            if self.view_mode != view {
+                log::debug!("view {:?} -> {:?}", view, self.view_mode);
+            }
+            if self.input_mode != mode {
+                log::debug!("input mode {} -> {}", mode, self.input_mode);
+            }
+        }
+        if let Some(task) = self.task.take() {
+            task.cancel();
         }
         Ok(())
     }
 
     fn set_countries(&mut self) -> Result<(), AppError> {
-        let output = std::process::Command::new("nordvpn")
-            .arg("countries")
-            .output()?;
+        self.countries = self.backend.countries()?;
+        Ok(())
+    }
 
-        self.countries = String::from_utf8(output.stdout)?
-            .split_whitespace()
-            .map(|s| s.to_string())
-            .collect();
+    fn set_cities(&mut self) -> Result<(), AppError> {
+        let Some(country) = self.selected_country().cloned() else {
+            return Ok(());
+        };
+        self.cities = self.backend.cities(&country)?;
+        Ok(())
+    }
 
+    /// Loads the cities of the selected country and highlights the first
+    /// one, just below the "Any city" row.
+    fn open_cities(&mut self) -> Result<(), AppError> {
+        self.set_cities()?;
+        self.city_index = self.cities.len().min(1);
+        self.state.select(Some(self.city_index));
         Ok(())
     }
 
-    fn set_cities(&mut self) -> Result<(), AppError> {
-        let output = std::process::Command::new("nordvpn")
-            .arg("cities")
-            .arg(&self.countries[self.country_index])
-            .output()?;
-
-        self.cities = String::from_utf8(output.stdout)?
-            .split_whitespace()
-            .map(|s| s.to_string())
-            .collect();
+    fn set_groups(&mut self) -> Result<(), AppError> {
+        self.groups = self.backend.groups()?;
+        Ok(())
+    }
 
+    fn set_settings(&mut self) -> Result<(), AppError> {
+        self.settings = self.backend.settings()?;
         Ok(())
     }
 
-    fn connect(&mut self) -> Result<View, AppError> {
-        let output = std::process::Command::new("nordvpn")
-            .arg("connect")
-            .arg(&self.cities[self.city_index])
-            .output()?;
+    fn connect(&mut self) {
+        let location = match self.view_mode {
+            View::GroupCountries => match self.groups.get(self.group_index) {
+                Some(group) => Location::Group {
+                    group: group.clone(),
+                    // Index 0 is the "Any country" entry.
+                    country: self
+                        .group_country_index
+                        .checked_sub(1)
+                        .and_then(|i| self.countries.get(i))
+                        .cloned(),
+                },
+                None => return,
+            },
+            View::Favorites => match self.favorites.items().get(self.favorite_index) {
+                Some(favorite) => favorite.location(),
+                None => return,
+            },
+            View::History => match self.history.recent().nth(self.history_index) {
+                Some(entry) => entry.location.clone(),
+                None => return,
+            },
+            _ => match (self.selected_city(), self.selected_country()) {
+                (Some(city), _) => Location::City(city.clone()),
+                // The "Any city" row lets NordVPN pick the server.
+                (None, Some(country)) => Location::Country(country.clone()),
+                (None, None) => return,
+            },
+        };
+        self.start(Operation::Connect(location));
+    }
 
-        self.connected = output.status.success();
-        self.connection_output = String::from_utf8(output.stdout)?
-            .lines()
-            .map(|s| s.to_string())
-            .collect();
+    /// Connects to the highlighted country, or the one whose cities are shown.
+    fn connect_country(&mut self) {
+        if !matches!(self.view_mode, View::Countries | View::Cities) {
+            return;
+        }
+        if let Some(country) = self.selected_country().cloned() {
+            self.start(Operation::Connect(Location::Country(country)));
+        }
+    }
 
-        if output.status.success() {
-            Ok(View::Connection)
-        } else {
-            Err(AppError::Command(output.status))
+    /// The favorite matching `name` in the current country/city list.
+    fn favorite_for(&self, name: &str) -> Option<Favorite> {
+        match self.view_mode {
+            View::Countries => Some(Favorite {
+                country: name.to_string(),
+                city: None,
+            }),
+            View::Cities => Some(Favorite {
+                country: self.selected_country()?.clone(),
+                city: Some(name.to_string()),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Stars or unstars the highlighted country, city or favorite.
+    fn toggle_favorite(&mut self) -> Result<(), AppError> {
+        let favorite = match self.view_mode {
+            View::Countries => self.selected_country().and_then(|c| self.favorite_for(c)),
+            View::Cities => self.selected_city().and_then(|c| self.favorite_for(c)),
+            View::Favorites => self.favorites.items().get(self.favorite_index).cloned(),
+            _ => None,
+        };
+        if let Some(favorite) = favorite {
+            self.favorites.toggle(favorite)?;
+            // Keep the selection inside the list after a removal.
+            self.move_cursor(|index, _| index);
         }
+        Ok(())
     }
 
-    fn disconnect(&mut self) -> Result<(), AppError> {
-        let output = std::process::Command::new("nordvpn")
-            .arg("disconnect")
-            .output()?;
+    /// Connects again to wherever the last successful connection went.
+    fn reconnect(&mut self) {
+        if let Some(location) = self.history.last_location().cloned() {
+            self.start(Operation::Connect(location));
+        }
+    }
 
-        self.connected = !output.status.success();
-        self.connection_output = String::from_utf8(output.stdout)?
-            .lines()
-            .map(|s| s.to_string())
-            .collect();
+    fn disconnect(&mut self) {
+        self.start(Operation::Disconnect);
+    }
 
-        if output.status.success() {
-            Ok(())
-        } else {
-            Err(AppError::Command(output.status))
+    /// Flips the highlighted setting, unless the CLI did not report it.
+    fn toggle_setting(&mut self) {
+        let toggle = Toggle::ALL[self.settings_index];
+        if let Some(enabled) = self.settings.get(toggle) {
+            self.start(Operation::Set(Change::Toggle(toggle, !enabled)));
         }
     }
 
-    fn draw(&mut self, f: &mut Frame) {
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .margin(1)
-            .constraints(
-                [
-                    Constraint::Length(3),
-                    Constraint::Min(0),
-                    Constraint::Length(3),
-                ]
-                .as_ref(),
-            )
-            .split(f.area());
+    /// Applies the highlighted technology/protocol, unless it clashes with
+    /// the current settings.
+    fn apply_choice(&mut self) {
+        let choice = Choice::ALL[self.technology_index];
+        if !choice.is_active(&self.settings) && choice.conflict(&self.settings).is_none() {
+            self.start(Operation::Set(choice.change()));
+        }
+    }
 
-        let title_text = if self.connected {
-            Line::from("Connected").style(Style::default().fg(self.config.colors.connected))
+    fn start(&mut self, operation: Operation) {
+        if self.task.is_none() {
+            log::info!("{} started", operation);
+            self.task = Some(Task::spawn(self.backend.clone(), operation));
+        }
+    }
+
+    /// Runs once per loop iteration, after input has been handled.
+    pub(crate) fn tick(&mut self) -> Result<(), AppError> {
+        self.poll_task()?;
+        // A failed background refresh keeps the last known status on screen.
+        if let Some(Ok(status)) = self.poller.tick(&self.backend) {
+            if status.state != self.status.state {
+                log::info!("status {} -> {}", self.status.state, status.state);
+            }
+            self.status = status;
+            self.status_at = Instant::now();
+        }
+        match self.config_watcher.as_mut().and_then(|w| w.poll()) {
+            Some(Ok(config)) => {
+                log::info!("config reloaded");
+                self.apply_config(config);
+            }
+            Some(Err(e)) => {
+                log::warn!("config not reloaded: {}", e);
+                self.config_error = Some(e);
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Swaps in a reloaded config, leaving the view and selection alone.
+    fn apply_config(&mut self, mut config: config::Config) {
+        config.colors = config.colors.downgrade(self.color_support);
+        self.config = config;
+        self.config_error = None;
+        self.pending_keys.clear();
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn status(&self) -> &Status {
+        &self.status
+    }
+
+    /// Title and lines of the error popup, if one is up.
+    #[allow(dead_code)]
+    pub(crate) fn error(&self) -> Option<(&str, &[String])> {
+        self.error
+            .as_ref()
+            .map(|error| (error.title.as_str(), error.lines.as_slice()))
+    }
+
+    pub(crate) fn refresh_status(&mut self) {
+        self.poller.refresh_now();
+    }
+
+    pub(crate) fn is_busy(&self) -> bool {
+        self.task.is_some()
+    }
+
+    /// Picks up the result of the running task, if it is done.
+    fn poll_task(&mut self) -> Result<(), AppError> {
+        let (operation, result, elapsed) = match &self.task {
+            Some(task) => match task.poll() {
+                Some(result) => (task.operation.clone(), result, task.elapsed()),
+                None => return Ok(()),
+            },
+            None => return Ok(()),
+        };
+        self.task = None;
+        match &result {
+            Ok(output) => log::info!(
+                "{} finished in {:.2?}: {}",
+                operation,
+                elapsed,
+                output.status
+            ),
+            Err(e) => log::warn!("{} failed after {:.2?}: {:?}", operation, elapsed, e),
+        }
+
+        let entry = match &operation {
+            Operation::Connect(location) => {
+                let outcome = match &result {
+                    Ok(output) if output.status.success() => Outcome::Connected,
+                    Err(AppError::Cancelled) => Outcome::Cancelled,
+                    _ => Outcome::Failed,
+                };
+                Some(Entry::new(location.clone(), outcome, elapsed))
+            }
+            _ => None,
+        };
+
+        let finished = self.finish_task(operation, result);
+        // Losing a history entry is not worth hiding what the task did.
+        if let Some(Err(e)) = entry.map(|entry| self.history.record(entry)) {
+            log::warn!("could not record the connection history: {}", e);
+        }
+        finished
+    }
+
+    /// Shows what `operation` did and moves to the view that follows it.
+    fn finish_task(
+        &mut self,
+        operation: Operation,
+        result: Result<CommandOutput, AppError>,
+    ) -> Result<(), AppError> {
+        let output = match result {
+            Err(AppError::Cancelled) => {
+                self.connection_output = vec![format!("{} cancelled", operation)];
+                self.refresh_status();
+                return Ok(());
+            }
+            result => result?,
+        };
+
+        self.connection_output = output.lines();
+        self.refresh_status();
+        match operation {
+            Operation::Connect(_) if output.status.success() => {
+                self.clear_search();
+                self.view_mode = View::Connection;
+            }
+            Operation::Set(..) => self.set_settings()?,
+            _ => {}
+        }
+
+        if !output.status.success() {
+            let title = format!("{} failed ({})", operation, output.status);
+            let error = AppError::from_output(output);
+            // Recognised failures are titled with what went wrong instead.
+            let recognised = matches!(error, AppError::NordVpn(..));
+            self.show_error(error);
+            if let (Some(popup), false) = (&mut self.error, recognised) {
+                popup.title = title;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn draw(&mut self, f: &mut Frame) {
+        let title_text = if let Some(task) = &self.task {
+            let mut text = format!(
+                "{} {}... {}s",
+                task.spinner(),
+                task.operation,
+                task.elapsed().as_secs()
+            );
+            if let (true, Some(keys)) = (
+                task.operation.is_cancellable(),
+                self.config.keys.describe(Action::Cancel),
+            ) {
+                text.push_str(&format!(" <{}> to cancel", keys));
+            }
+            Line::from(text)
         } else {
-            Line::from("Disconnected").style(Style::default().fg(self.config.colors.disconnected))
+            let mut summary = self.status.summary();
+            if let Some(connection_type) = self.settings.connection_type() {
+                summary.push_str(&format!(" | {}", connection_type));
+            }
+            Line::from(summary).style(self.state_style())
         };
 
         let title = Title::from(title_text.alignment(Alignment::Center));
 
         let instructions = match self.input_mode {
-            InputMode::Normal => Title::from(
-                Line::from(vec![
-                    " Normal | ".bold(),
-                    " Select ".bold(),
-                    "<Enter>".into(),
-                    " Down ".bold(),
-                    "<J | Down>".into(),
-                    " Up ".bold(),
-                    "<K | Up>".into(),
-                    " Quit ".bold(),
-                    "<Q | Esc>".into(),
-                    " Disconnect ".bold(),
-                    "<D>".into(),
-                ])
-                .style(Style::default().fg(self.config.colors.normal_mode)),
-            ),
+            InputMode::Normal => {
+                // Only what the current view is about; `?` lists the rest.
+                let mut actions = vec![Action::Help];
+                if self
+                    .task
+                    .as_ref()
+                    .is_some_and(|task| task.operation.is_cancellable())
+                {
+                    actions.push(Action::Cancel);
+                }
+                actions.extend(match self.view_mode {
+                    View::Countries => &[
+                        Action::Select,
+                        Action::ConnectCountry,
+                        Action::Search,
+                        Action::Favorite,
+                    ][..],
+                    View::Cities => &[
+                        Action::Select,
+                        Action::Back,
+                        Action::Search,
+                        Action::Favorite,
+                    ],
+                    View::Connection => &[Action::Select, Action::Disconnect],
+                    View::Settings => &[Action::Toggle, Action::Technology, Action::Back],
+                    View::Favorites => &[Action::Select, Action::Favorite, Action::Back],
+                    View::History => &[Action::Select, Action::Reconnect, Action::Back],
+                    View::Technology | View::Groups | View::GroupCountries | View::Doctor => {
+                        &[Action::Select, Action::Back]
+                    }
+                    View::Help => &[Action::Back],
+                });
+                actions.push(Action::Quit);
+
+                let mut instructions = vec![" Normal | ".bold()];
+                for action in actions {
+                    // Unbound actions are left out.
+                    if let Some(keys) = self.config.keys.describe(action) {
+                        instructions.push(format!(" {} ", action.label()).bold());
+                        instructions.push(format!("<{}>", keys).into());
+                    }
+                }
+                Title::from(
+                    Line::from(instructions)
+                        .style(Style::default().fg(self.config.colors.normal_mode)),
+                )
+            }
             InputMode::Search => {
                 let search_text = format!(" Search: {} | ", self.search_string);
                 let instructions = vec![
@@ -229,6 +675,26 @@ impl App {
                         .style(Style::default().fg(self.config.colors.search_mode)),
                 )
             }
+            InputMode::Server => {
+                let mut instructions = vec![
+                    format!(" Server: {} | ", self.server_input).into(),
+                    " Connect ".bold(),
+                    "<Enter>".into(),
+                    " Cancel ".bold(),
+                    "<Esc>".into(),
+                ];
+                if let Some(error) = &self.server_error {
+                    instructions.push(
+                        format!(" | {} ", error)
+                            .fg(self.config.colors.disconnected)
+                            .bold(),
+                    );
+                }
+                Title::from(
+                    Line::from(instructions)
+                        .style(Style::default().fg(self.config.colors.search_mode)),
+                )
+            }
         };
 
         let block = Block::bordered()
@@ -241,64 +707,408 @@ impl App {
             .bg(self.config.colors.background)
             .border_set(border::THICK);
 
+        let mut inner = block.inner(f.area());
+        f.render_widget(block, f.area());
+
+        if let Some(error) = &self.config_error {
+            let [body, line] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(inner);
+            let line_text = Line::from(format!(" Config not reloaded: {} ", error)).style(
+                Style::default()
+                    .fg(self.config.colors.disconnected)
+                    .add_modifier(Modifier::BOLD),
+            );
+            f.render_widget(Paragraph::new(line_text), line);
+            inner = body;
+        }
+
+        let [main, side] =
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(STATUS_PANE_WIDTH)])
+                .areas(inner);
+
         match self.view_mode {
-            View::Countries | View::Cities => self.draw_lists(f, chunks[1], block),
-            View::Connection => self.draw_connection(f, chunks[1], block),
+            View::Countries
+            | View::Cities
+            | View::Groups
+            | View::GroupCountries
+            | View::Favorites => self.draw_lists(f, main),
+            View::Connection => self.draw_connection(f, main),
+            View::Settings => self.draw_settings(f, main),
+            View::Technology => self.draw_technology(f, main),
+            View::History => self.draw_history(f, main),
+            View::Help => self.draw_help(f, main),
+            View::Doctor => self.draw_doctor(f, main),
+        }
+        self.draw_status(f, side);
+
+        if let Some(error) = &self.error {
+            self.draw_error(f, error, inner);
+        }
+    }
+
+    /// Connected/disconnected color, plus bold and underline in monochrome
+    /// mode where the colors are gone.
+    fn state_style(&self) -> Style {
+        let colors = &self.config.colors;
+        match (
+            self.status.is_connected(),
+            self.color_support.is_monochrome(),
+        ) {
+            (true, true) => Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            (true, false) => Style::default().fg(colors.connected),
+            (false, _) => Style::default().fg(colors.disconnected),
+        }
+    }
+
+    /// Style of the selected row of every list.
+    fn highlight_style(&self) -> Style {
+        let style = Style::default()
+            .add_modifier(Modifier::BOLD)
+            .add_modifier(Modifier::ITALIC);
+        if self.color_support.is_monochrome() {
+            style.add_modifier(Modifier::REVERSED)
+        } else {
+            style
         }
     }
 
-    fn draw_lists(&mut self, f: &mut Frame, _area: Rect, block: Block) {
+    fn draw_status(&self, f: &mut Frame, area: Rect) {
+        let colors = &self.config.colors;
+        let state_style = self.state_style();
+
+        // Keep the clock moving between two refreshes.
+        let rows = self.status.rows(self.status_at.elapsed());
+
+        let mut lines: Vec<Line> = rows
+            .into_iter()
+            .enumerate()
+            .map(|(i, (label, value))| {
+                let value_style = if i == 0 {
+                    state_style
+                } else {
+                    Style::default().fg(colors.connection_output)
+                };
+                Line::from(vec![
+                    Span::from(format!("{:>11} ", label)).fg(colors.items),
+                    Span::styled(value, value_style),
+                ])
+            })
+            .collect();
+
+        let warnings: Vec<String> = self
+            .config
+            .warnings
+            .iter()
+            .cloned()
+            .chain(self.favorites.warning())
+            .collect();
+        if !warnings.is_empty() {
+            lines.push(Line::default());
+            for warning in warnings {
+                lines.push(Line::from(format!("⚠ {}", warning)).fg(colors.disconnected));
+            }
+        }
+
+        let pane = Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+            Block::bordered()
+                .title(" Status ")
+                .border_style(Style::default().fg(colors.items)),
+        );
+        f.render_widget(pane, area);
+    }
+
+    fn draw_error(&self, f: &mut Frame, error: &ErrorPopup, area: Rect) {
+        let colors = &self.config.colors;
+        let width = area.width.min(72);
+        // Wrapped lines, plus the borders.
+        let text_width = width.saturating_sub(2).max(1) as usize;
+        let height = error
+            .lines
+            .iter()
+            .map(|line| line.chars().count().max(1).div_ceil(text_width) as u16)
+            .sum::<u16>()
+            .saturating_add(2)
+            .min(area.height);
+
+        let [popup] = Layout::horizontal([Constraint::Length(width)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::Center)
+            .areas(popup);
+
+        let dismiss = [Action::Select, Action::Cancel]
+            .iter()
+            .filter_map(|action| self.config.keys.describe(*action))
+            .collect::<Vec<_>>()
+            .join(" | ");
+        let lines: Vec<Line> = error.lines.iter().map(|l| Line::from(l.as_str())).collect();
+        let block = Block::bordered()
+            .title(format!(" {} ", error.title).bold())
+            .title(
+                Title::from(format!(" Dismiss <{}> ", dismiss))
+                    .alignment(Alignment::Center)
+                    .position(Position::Bottom),
+            )
+            .border_set(border::THICK)
+            .border_style(Style::default().fg(colors.disconnected))
+            .bg(colors.background);
+
+        f.render_widget(Clear, popup);
+        f.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .style(Style::default().fg(colors.items))
+                .block(block),
+            popup,
+        );
+    }
+
+    fn draw_lists(&mut self, f: &mut Frame, area: Rect) {
         let mut list = Vec::<ListItem>::new();
 
-        let l = match self.view_mode {
-            View::Countries => self
-                .countries
-                .clone()
+        // Each entry comes with the char positions matched by the search.
+        let l: Vec<(String, Vec<usize>)> = match self.view_mode {
+            View::Countries => fuzzy::filter(&self.search_string, &self.countries)
                 .into_iter()
-                .filter(|c| c.to_lowercase().contains(&self.search_string))
+                .map(|(i, m)| (self.countries[i].clone(), m.positions))
                 .collect(),
-            View::Cities => self
-                .cities
-                .clone()
-                .into_iter()
-                .filter(|c| c.to_lowercase().contains(&self.search_string))
+            View::Cities => std::iter::once((ANY_CITY.to_string(), Vec::new()))
+                .chain(
+                    fuzzy::filter(&self.search_string, &self.cities)
+                        .into_iter()
+                        .map(|(i, m)| (self.cities[i].clone(), m.positions)),
+                )
+                .collect(),
+            View::Groups => self
+                .groups
+                .iter()
+                .map(|g| (g.clone(), Vec::new()))
+                .collect(),
+            View::GroupCountries => std::iter::once("Any country".to_string())
+                .chain(self.countries.iter().cloned())
+                .map(|c| (c, Vec::new()))
+                .collect(),
+            View::Favorites => self
+                .favorites
+                .items()
+                .iter()
+                .map(|f| (f.to_string(), Vec::new()))
                 .collect(),
             _ => Vec::new(),
         };
-        for (i, country) in l.iter().enumerate() {
-            let style = match self.view_mode {
-                View::Countries => {
-                    if i == self.country_index {
-                        Style::default().fg(self.config.colors.items_selected)
-                    } else {
-                        Style::default().fg(self.config.colors.items)
-                    }
-                }
-                View::Cities => {
-                    if i == self.city_index {
-                        Style::default().fg(self.config.colors.items_selected)
-                    } else {
-                        Style::default().fg(self.config.colors.items)
-                    }
-                }
-                _ => Style::default().fg(self.config.colors.items),
+        let selected = self.cursor().map(|(index, _)| *index);
+        let highlight = Style::default()
+            .fg(self.config.colors.search_mode)
+            .add_modifier(Modifier::UNDERLINED);
+        for (i, (country, positions)) in l.iter().enumerate() {
+            let style = if Some(i) == selected {
+                Style::default().fg(self.config.colors.items_selected)
+            } else {
+                Style::default().fg(self.config.colors.items)
             };
+            let starred = self
+                .favorite_for(country)
+                .is_some_and(|f| self.favorites.contains(&f));
+            let mut spans = Vec::new();
+            if starred {
+                spans.push(Span::from("★ "));
+            }
+            spans.extend(highlight_matches(country, positions, highlight));
             list.push(ListItem::new(
-                Line::from(Span::from(country.to_string()))
-                    .alignment(Alignment::Center)
-                    .style(style),
+                Line::from(spans).alignment(Alignment::Center).style(style),
             ));
         }
 
-        let list = List::new(list).block(block).highlight_style(
-            Style::default()
-                .add_modifier(Modifier::BOLD)
-                .add_modifier(Modifier::ITALIC),
+        let list = List::new(list).highlight_style(self.highlight_style());
+        f.render_stateful_widget(list, area, &mut self.state);
+    }
+
+    fn draw_settings(&mut self, f: &mut Frame, area: Rect) {
+        let colors = &self.config.colors;
+
+        let list: Vec<ListItem> = Toggle::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, toggle)| {
+                let label_color = if i == self.settings_index {
+                    colors.items_selected
+                } else {
+                    colors.items
+                };
+                let (value, value_color) = match self.settings.get(*toggle) {
+                    Some(true) => ("on", colors.connected),
+                    Some(false) => ("off", colors.disconnected),
+                    None => ("n/a", colors.items),
+                };
+                ListItem::new(
+                    Line::from(vec![
+                        Span::from(format!("{:<20}", toggle.to_string())).fg(label_color),
+                        Span::from(format!("{:>4}", value)).fg(value_color),
+                    ])
+                    .alignment(Alignment::Center),
+                )
+            })
+            .collect();
+
+        let list = List::new(list).highlight_style(self.highlight_style());
+        f.render_stateful_widget(list, area, &mut self.state);
+    }
+
+    fn draw_technology(&mut self, f: &mut Frame, area: Rect) {
+        let colors = &self.config.colors;
+
+        let list: Vec<ListItem> = Choice::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, choice)| {
+                let kind = match choice {
+                    Choice::Technology(_) => "Technology",
+                    Choice::Protocol(_) => "Protocol",
+                };
+                let label_color = if i == self.technology_index {
+                    colors.items_selected
+                } else {
+                    colors.items
+                };
+                let label = Span::from(format!("{:<12}{:<14}", kind, choice.to_string()));
+
+                let line = if let Some(conflict) = choice.conflict(&self.settings) {
+                    // Greyed out, with the reason next to it.
+                    Line::from(vec![
+                        label.fg(label_color),
+                        Span::from(conflict).fg(colors.items),
+                    ])
+                    .add_modifier(Modifier::DIM)
+                } else if choice.is_active(&self.settings) {
+                    Line::from(vec![
+                        label.fg(label_color),
+                        Span::from("active").fg(colors.connected),
+                    ])
+                } else {
+                    Line::from(label.fg(label_color))
+                };
+                ListItem::new(line)
+            })
+            .collect();
+
+        let list = List::new(list).highlight_style(self.highlight_style());
+        f.render_stateful_widget(list, area, &mut self.state);
+    }
+
+    fn draw_history(&mut self, f: &mut Frame, area: Rect) {
+        let colors = &self.config.colors;
+
+        let list: Vec<ListItem> = self
+            .history
+            .recent()
+            .enumerate()
+            .map(|(i, entry)| {
+                let (marker, marker_color) = match entry.outcome {
+                    Outcome::Connected => ("✔", colors.connected),
+                    Outcome::Failed => ("✘", colors.disconnected),
+                    Outcome::Cancelled => ("–", colors.items),
+                };
+                let label_color = if i == self.history_index {
+                    colors.items_selected
+                } else {
+                    colors.items
+                };
+                ListItem::new(
+                    Line::from(vec![
+                        Span::from(format!("{} ", marker)).fg(marker_color),
+                        Span::from(format!("{:<30}", entry.location.to_string())).fg(label_color),
+                        Span::from(format!(
+                            "{:>10} {:>6.1}s",
+                            entry.age(),
+                            entry.duration_ms as f64 / 1000.0
+                        ))
+                        .fg(colors.items),
+                    ])
+                    .alignment(Alignment::Center),
+                )
+            })
+            .collect();
+
+        let list = List::new(list).highlight_style(self.highlight_style());
+        f.render_stateful_widget(list, area, &mut self.state);
+    }
+
+    fn draw_help(&mut self, f: &mut Frame, area: Rect) {
+        let colors = &self.config.colors;
+
+        let list: Vec<ListItem> = Action::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, action)| {
+                let label_color = if i == self.help_index {
+                    colors.items_selected
+                } else {
+                    colors.items
+                };
+                let keys = self
+                    .config
+                    .keys
+                    .describe(*action)
+                    .unwrap_or_else(|| "unbound".to_string());
+                ListItem::new(
+                    Line::from(vec![
+                        Span::from(format!("{:<18}", action.label())).fg(label_color),
+                        Span::from(format!("{:<16}", keys)).fg(colors.normal_mode),
+                        Span::from(format!("{:>16}", action.name())).fg(colors.items),
+                    ])
+                    .alignment(Alignment::Center),
+                )
+            })
+            .collect();
+
+        let list = List::new(list).highlight_style(self.highlight_style());
+        f.render_stateful_widget(list, area, &mut self.state);
+    }
+
+    fn draw_doctor(&mut self, f: &mut Frame, area: Rect) {
+        let colors = &self.config.colors;
+        let [list_area, hint] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(area);
+
+        let list: Vec<ListItem> = self
+            .checks
+            .iter()
+            .map(|check| {
+                let verdict_color = match check.verdict {
+                    Verdict::Pass => colors.connected,
+                    Verdict::Warn => colors.search_mode,
+                    Verdict::Fail => colors.disconnected,
+                    Verdict::Skip => colors.items,
+                };
+                let mut lines = vec![Line::from(vec![
+                    Span::from(format!(" {} ", check.verdict.symbol()))
+                        .fg(verdict_color)
+                        .bold(),
+                    Span::from(format!("{:<16}", check.name)).fg(colors.items_selected),
+                    Span::from(check.detail.clone()).fg(colors.items),
+                ])];
+                if let Some(remedy) = check.remedy {
+                    lines.push(Line::from(format!("{:19}{}", "", remedy)).fg(colors.normal_mode));
+                }
+                ListItem::new(lines)
+            })
+            .collect();
+        let list = List::new(list).highlight_style(self.highlight_style());
+        f.render_stateful_widget(list, list_area, &mut self.state);
+
+        let text = if doctor::passed(&self.checks) {
+            "All checks passed."
+        } else {
+            "Fix the failed checks, then select any line to check again."
+        };
+        f.render_widget(
+            Paragraph::new(Line::from(text).fg(colors.items)).alignment(Alignment::Center),
+            hint,
         );
-        f.render_stateful_widget(list, f.area(), &mut self.state);
     }
 
-    fn draw_connection(&mut self, f: &mut Frame, _area: Rect, block: Block) {
+    fn draw_connection(&mut self, f: &mut Frame, area: Rect) {
         let mut list = Vec::<ListItem>::new();
 
         for line in self.connection_output.iter() {
@@ -309,102 +1119,207 @@ impl App {
             ));
         }
 
-        let list = List::new(list).block(block).highlight_style(
-            Style::default()
-                .add_modifier(Modifier::BOLD)
-                .add_modifier(Modifier::ITALIC),
-        );
-        f.render_widget(list, f.area());
+        let list = List::new(list).highlight_style(self.highlight_style());
+        f.render_widget(list, area);
     }
 
     fn handle_events(&mut self) -> Result<(), AppError> {
+        // Wake up at least every tick to redraw the spinner and pick up
+        // background results, even when no key is pressed.
+        if !event::poll(TICK_RATE)? {
+            return Ok(());
+        }
         if let Event::Key(key_event) = event::read()? {
             if key_event.kind == KeyEventKind::Press {
-                self.handle_key_event(key_event)?
+                if let Err(e) = self.handle_key_event(key_event) {
+                    self.show_error(e);
+                }
             }
         }
         Ok(())
     }
 
-    fn handle_key_event(&mut self, event: KeyEvent) -> Result<(), AppError> {
+    pub(crate) fn handle_key_event(&mut self, event: KeyEvent) -> Result<(), AppError> {
+        if self.error.is_some() {
+            self.handle_error_popup(event);
+            return Ok(());
+        }
         match self.input_mode {
             InputMode::Normal => self.handle_normal_mode(event)?,
             InputMode::Search => self.handle_search_mode(event)?,
+            InputMode::Server => self.handle_server_mode(event),
         }
         Ok(())
     }
 
     fn handle_normal_mode(&mut self, event: KeyEvent) -> Result<(), AppError> {
-        match event.code {
-            event::KeyCode::Esc | event::KeyCode::Char('q') => self.exit = true,
-            event::KeyCode::Enter => {
-                self.search_string.clear();
+        let Some(action) = self.config.keys.resolve(&mut self.pending_keys, event) else {
+            return Ok(());
+        };
+        log::trace!("action {}", action.name());
+        match action {
+            Action::Cancel if self.is_busy() => {
+                if let Some(task) = self.task.as_ref().filter(|t| t.operation.is_cancellable()) {
+                    task.cancel();
+                }
+            }
+            Action::Cancel | Action::Quit => self.exit = true,
+            Action::Select => {
+                self.clear_search();
                 self.view_mode = match self.view_mode {
                     View::Countries => {
-                        self.state.select(Some(0));
-                        self.set_cities()?;
-                        self.city_index = 0;
+                        self.open_cities()?;
+                        View::Cities
+                    }
+                    View::Cities => {
+                        self.connect();
                         View::Cities
                     }
-                    View::Cities => self.connect()?,
                     View::Connection => {
                         self.country_index = 0;
                         self.city_index = 0;
                         self.set_countries()?;
                         View::Countries
                     }
+                    View::Settings => View::Settings,
+                    View::Technology => {
+                        self.apply_choice();
+                        View::Technology
+                    }
+                    // NordVPN may offer no groups at all.
+                    View::Groups if self.groups.is_empty() => View::Groups,
+                    View::Groups => {
+                        self.set_countries()?;
+                        self.group_country_index = 0;
+                        self.state.select(Some(0));
+                        View::GroupCountries
+                    }
+                    View::GroupCountries => {
+                        self.connect();
+                        View::GroupCountries
+                    }
+                    View::Favorites => {
+                        self.connect();
+                        View::Favorites
+                    }
+                    View::History => {
+                        self.connect();
+                        View::History
+                    }
+                    View::Help => View::Help,
+                    View::Doctor => {
+                        self.recheck()?;
+                        self.view_mode
+                    }
                 };
             }
-            event::KeyCode::Char('D') => self.disconnect()?,
-            event::KeyCode::Down | event::KeyCode::Char('j') => self.increment_index(),
-            event::KeyCode::Up | event::KeyCode::Char('k') => self.decrement_index(),
-            event::KeyCode::Char('G') => match self.view_mode {
-                View::Countries => {
-                    self.country_index = self.countries.len() - 1;
-                    self.state.select(Some(self.country_index));
-                }
-                View::Cities => {
-                    self.city_index = self.cities.len() - 1;
-                    self.state.select(Some(self.city_index))
-                }
-                _ => {}
-            },
-            //TODO: fix `gg` keybind
-            event::KeyCode::Char('g') => {
-                if self.awaiting_second_g {
-                    match self.view_mode {
-                        View::Countries => {
-                            self.country_index = 0;
-                            self.state.select(Some(self.country_index));
-                        }
-                        View::Cities => {
-                            self.city_index = 0;
-                            self.state.select(Some(self.city_index));
-                        }
-                        _ => {}
-                    }
-                    self.awaiting_second_g = false;
-                } else {
-                    self.awaiting_second_g = true;
+            Action::Disconnect => self.disconnect(),
+            Action::ConnectCountry => self.connect_country(),
+            Action::QuickConnect => self.start(Operation::Connect(Location::Recommended)),
+            Action::ConnectServer => {
+                self.server_input.clear();
+                self.server_error = None;
+                self.input_mode = InputMode::Server;
+            }
+            Action::Technology => {
+                self.clear_search();
+                self.set_settings()?;
+                self.technology_index = 0;
+                self.state.select(Some(0));
+                self.view_mode = View::Technology;
+            }
+            Action::Reconnect => self.reconnect(),
+            Action::History => {
+                self.clear_search();
+                self.history_index = 0;
+                self.state.select(Some(0));
+                self.view_mode = View::History;
+            }
+            Action::Favorite => self.toggle_favorite()?,
+            Action::Favorites => {
+                self.clear_search();
+                self.favorite_index = 0;
+                self.state.select(Some(0));
+                self.view_mode = View::Favorites;
+            }
+            Action::Groups => {
+                self.clear_search();
+                self.set_groups()?;
+                self.group_index = 0;
+                self.state.select(Some(0));
+                self.view_mode = View::Groups;
+            }
+            Action::Settings => {
+                self.clear_search();
+                self.set_settings()?;
+                self.settings_index = 0;
+                self.state.select(Some(0));
+                self.view_mode = View::Settings;
+            }
+            Action::Toggle => {
+                if let View::Settings = self.view_mode {
+                    self.toggle_setting();
                 }
             }
-            event::KeyCode::Char('/') | event::KeyCode::Char('i') => {
+            Action::Down => self.increment_index(),
+            Action::Up => self.decrement_index(),
+            Action::Bottom => self.move_cursor(|_, len| len.saturating_sub(1)),
+            Action::Top => self.move_cursor(|_, _| 0),
+            Action::Doctor => {
+                self.clear_search();
+                self.recheck()?;
+                self.doctor_index = 0;
+                self.state.select(Some(0));
+                self.view_mode = View::Doctor;
+            }
+            Action::Help => {
+                self.clear_search();
+                self.help_index = 0;
+                self.state.select(Some(0));
+                self.view_mode = View::Help;
+            }
+            // Only the country and city lists can be searched.
+            Action::Search if matches!(self.view_mode, View::Countries | View::Cities) => {
                 self.input_mode = InputMode::Search;
             }
-            event::KeyCode::Char('h') => match self.view_mode {
+            Action::Search => {}
+            Action::Back => match self.view_mode {
                 View::Cities => {
+                    self.clear_search();
                     self.set_countries()?;
                     self.city_index = 0;
+                    self.state.select(Some(self.country_index));
                     self.view_mode = View::Countries;
                 }
                 View::Connection => {
-                    self.set_cities()?;
-                    self.city_index = 0;
+                    self.open_cities()?;
                     self.view_mode = View::Cities;
                 }
+                View::Settings => {
+                    self.set_countries()?;
+                    self.state.select(Some(self.country_index));
+                    self.view_mode = View::Countries;
+                }
+                View::Technology => {
+                    self.state.select(Some(self.settings_index));
+                    self.view_mode = View::Settings;
+                }
+                View::Groups => {
+                    self.set_countries()?;
+                    self.state.select(Some(self.country_index));
+                    self.view_mode = View::Countries;
+                }
+                View::GroupCountries => {
+                    self.state.select(Some(self.group_index));
+                    self.view_mode = View::Groups;
+                }
+                View::Favorites | View::History | View::Help | View::Doctor => {
+                    self.set_countries()?;
+                    self.state.select(Some(self.country_index));
+                    self.view_mode = View::Countries;
+                }
                 _ => {}
             },
-            _ => {}
         }
         Ok(())
     }
@@ -415,8 +1330,8 @@ impl App {
                 self.input_mode = InputMode::Normal;
                 self.view_mode = match self.view_mode {
                     View::Countries => {
-                        self.set_cities()?;
-                        self.search_string.clear();
+                        self.clear_search();
+                        self.open_cities()?;
                         View::Cities
                     }
                     View::Cities => View::Cities, // self.connect()?,
@@ -424,86 +1339,241 @@ impl App {
                         self.country_index = 0;
                         View::Countries
                     }
+                    View::Settings => View::Settings,
+                    View::Technology => View::Technology,
+                    View::Groups => View::Groups,
+                    View::GroupCountries => View::GroupCountries,
+                    View::Favorites => View::Favorites,
+                    View::History => View::History,
+                    View::Help => View::Help,
+                    View::Doctor => View::Doctor,
                 };
             }
             event::KeyCode::Esc => {
                 self.input_mode = InputMode::Normal;
             }
             event::KeyCode::Char(c) => {
-                self.search_string.push(c);
-                self.state.select(Some(0));
-                match self.view_mode {
-                    View::Countries => {
-                        self.countries = self
-                            .countries
-                            .clone()
-                            .into_iter()
-                            .filter(|c| c.to_lowercase().contains(&self.search_string))
-                            .collect();
-                    }
-                    View::Cities => {
-                        self.cities = self
-                            .cities
-                            .clone()
-                            .into_iter()
-                            .filter(|c| c.to_lowercase().contains(&self.search_string))
-                            .collect();
-                    }
-                    View::Connection => {}
-                }
-                self.country_index = 0;
+                self.edit_search(|search| search.push(c));
+                // Like fzf, typing jumps to the best match.
+                let top = self.pinned_rows();
+                self.move_cursor(|_, _| top);
             }
             event::KeyCode::Backspace => {
-                self.search_string.pop();
-                self.set_countries()?;
-                self.country_index = 0;
+                self.edit_search(|search| {
+                    search.pop();
+                });
             }
             _ => {}
         }
         Ok(())
     }
 
-    fn decrement_country(&mut self) {
-        if self.country_index > 0 {
-            self.country_index -= 1;
+    /// Runs the preflight checks again, loading the lists once they pass
+    /// for the first time.
+    fn recheck(&mut self) -> Result<(), AppError> {
+        let config = self.config_watcher.as_ref().and_then(|w| w.arg());
+        self.checks = doctor::run(self.backend.as_ref(), config);
+        if doctor::passed(&self.checks) && self.countries.is_empty() {
+            self.load()?;
+            self.state.select(Some(0));
+            self.view_mode = View::Countries;
         }
-        self.state.select(Some(self.country_index));
+        Ok(())
     }
 
-    fn increment_country(&mut self) {
-        if self.country_index < self.countries.len() - 1 {
-            self.country_index += 1;
+    /// Shows `error` over the current view instead of ending the session.
+    fn show_error(&mut self, error: AppError) {
+        if let AppError::Cancelled = error {
+            return;
         }
-        self.state.select(Some(self.country_index));
+        log::warn!("{}", error);
+        self.pending_keys.clear();
+        self.error = Some(ErrorPopup::from(error));
     }
 
-    fn decrement_city(&mut self) {
-        if self.city_index > 0 {
-            self.city_index -= 1;
+    /// Only dismissing works while the popup is up.
+    fn handle_error_popup(&mut self, event: KeyEvent) {
+        if let Some(Action::Select | Action::Cancel | Action::Back | Action::Quit) =
+            self.config.keys.resolve(&mut self.pending_keys, event)
+        {
+            self.error = None;
+        }
+    }
+
+    fn handle_server_mode(&mut self, event: KeyEvent) {
+        match event.code {
+            event::KeyCode::Enter => match data::parse_server(&self.server_input) {
+                Some(server) => {
+                    self.input_mode = InputMode::Normal;
+                    self.start(Operation::Connect(Location::Server(server)));
+                }
+                None => {
+                    self.server_error = Some(format!(
+                        "\"{}\" is not a server name like de1234",
+                        self.server_input.trim()
+                    ));
+                }
+            },
+            event::KeyCode::Esc => self.input_mode = InputMode::Normal,
+            event::KeyCode::Char(c) => {
+                self.server_input.push(c);
+                self.server_error = None;
+            }
+            event::KeyCode::Backspace => {
+                self.server_input.pop();
+                self.server_error = None;
+            }
+            _ => {}
         }
-        self.state.select(Some(self.city_index));
     }
 
-    fn increment_city(&mut self) {
-        if self.city_index < self.cities.len() - 1 {
-            self.city_index += 1;
+    /// Indexes into `countries` or `cities` of the entries the current view
+    /// shows, filtered and ranked by the search query.
+    ///
+    /// The master lists are never filtered in place: `country_index` and
+    /// `city_index` point into this list while a query is active, and into
+    /// the master list otherwise, as leaving a view clears the query.
+    fn visible(&self) -> Vec<usize> {
+        let items = match self.view_mode {
+            View::Countries => &self.countries,
+            View::Cities => &self.cities,
+            _ => return Vec::new(),
+        };
+        fuzzy::filter(&self.search_string, items)
+            .into_iter()
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Rows above the search results: the "Any city" entry of city lists.
+    fn pinned_rows(&self) -> usize {
+        match self.view_mode {
+            View::Cities => 1,
+            _ => 0,
         }
-        self.state.select(Some(self.city_index));
     }
 
-    fn decrement_index(&mut self) {
+    /// Master-list index of the highlighted country or city, `None` on a
+    /// pinned row.
+    fn selected(&self) -> Option<usize> {
+        let index = match self.view_mode {
+            View::Countries => self.country_index,
+            View::Cities => self.city_index,
+            _ => return None,
+        };
+        let index = index.checked_sub(self.pinned_rows())?;
+        self.visible().get(index).copied()
+    }
+
+    fn selected_country(&self) -> Option<&String> {
         match self.view_mode {
-            View::Countries => self.decrement_country(),
-            View::Cities => self.decrement_city(),
-            _ => {}
+            View::Countries => self.selected(),
+            _ => Some(self.country_index),
         }
+        .and_then(|i| self.countries.get(i))
     }
 
-    fn increment_index(&mut self) {
+    /// The highlighted city, only ever set in the city view.
+    fn selected_city(&self) -> Option<&String> {
+        if self.view_mode != View::Cities {
+            return None;
+        }
+        self.selected().and_then(|i| self.cities.get(i))
+    }
+
+    /// Edits the search query, keeping the highlighted country or city
+    /// selected when it still matches.
+    fn edit_search(&mut self, edit: impl FnOnce(&mut String)) {
+        let selected = self.selected();
+        edit(&mut self.search_string);
+        let pinned = self.pinned_rows();
+        let position = match selected {
+            Some(selected) => self
+                .visible()
+                .iter()
+                .position(|&i| i == selected)
+                .map_or(pinned, |position| position + pinned),
+            None => 0,
+        };
+        self.move_cursor(|_, _| position);
+    }
+
+    fn clear_search(&mut self) {
+        if !self.search_string.is_empty() {
+            self.edit_search(String::clear);
+        }
+    }
+
+    /// Selected index and length of the list shown by the current view.
+    fn cursor(&mut self) -> Option<(&mut usize, usize)> {
         match self.view_mode {
-            View::Countries => self.increment_country(),
-            View::Cities => self.increment_city(),
-            _ => {}
+            View::Countries => {
+                let len = self.visible().len();
+                Some((&mut self.country_index, len))
+            }
+            View::Cities => {
+                let len = self.visible().len() + self.pinned_rows();
+                Some((&mut self.city_index, len))
+            }
+            View::Settings => Some((&mut self.settings_index, Toggle::ALL.len())),
+            View::Technology => Some((&mut self.technology_index, Choice::ALL.len())),
+            View::Groups => Some((&mut self.group_index, self.groups.len())),
+            // One more for the "Any country" entry.
+            View::GroupCountries => Some((&mut self.group_country_index, self.countries.len() + 1)),
+            View::Favorites => Some((&mut self.favorite_index, self.favorites.items().len())),
+            View::History => Some((&mut self.history_index, self.history.recent().count())),
+            View::Help => Some((&mut self.help_index, Action::ALL.len())),
+            View::Doctor => Some((&mut self.doctor_index, self.checks.len())),
+            View::Connection => None,
         }
     }
+
+    /// Moves the selection of the current list to `to(index, len)`, clamped
+    /// to the list bounds.
+    fn move_cursor(&mut self, to: impl FnOnce(usize, usize) -> usize) {
+        let Some((index, len)) = self.cursor() else {
+            return;
+        };
+        *index = to(*index, len).min(len.saturating_sub(1));
+        let selected = *index;
+        self.state.select(Some(selected));
+    }
+
+    fn decrement_index(&mut self) {
+        self.move_cursor(|index, _| index.saturating_sub(1));
+    }
+
+    fn increment_index(&mut self) {
+        self.move_cursor(|index, _| index + 1);
+    }
+}
+
+/// Splits `text` into spans, styling the chars at `positions` with `highlight`.
+fn highlight_matches(text: &str, positions: &[usize], highlight: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+
+    for (i, c) in text.chars().enumerate() {
+        let matched = positions.contains(&i);
+        if matched != run_matched && !run.is_empty() {
+            let text = std::mem::take(&mut run);
+            spans.push(if run_matched {
+                Span::styled(text, highlight)
+            } else {
+                Span::from(text)
+            });
+        }
+        run_matched = matched;
+        run.push(c);
+    }
+    if !run.is_empty() {
+        spans.push(if run_matched {
+            Span::styled(run, highlight)
+        } else {
+            Span::from(run)
+        });
+    }
+
+    spans
 }
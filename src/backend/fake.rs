@@ -0,0 +1,353 @@
+use std::{
+    os::unix::process::ExitStatusExt,
+    process::ExitStatus,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    data::Location,
+    doctor::Check,
+    error::{AppError, NordVpnError},
+    settings::{Change, Choice, Protocol, Settings, Technology, Toggle},
+    status::{State, Status},
+};
+
+use super::{Backend, CancelToken, CommandOutput};
+
+const SAMPLE: &[(&str, &[&str])] = &[
+    ("Germany", &["Berlin", "Frankfurt", "Hamburg"]),
+    ("Italy", &["Milan", "Rome"]),
+    ("Japan", &["Osaka", "Tokyo"]),
+    ("Netherlands", &["Amsterdam"]),
+    ("Sweden", &["Stockholm"]),
+    ("United_Kingdom", &["Edinburgh", "London", "Manchester"]),
+    (
+        "United_States",
+        &["Chicago", "Dallas", "Los_Angeles", "New_York"],
+    ),
+];
+
+const GROUPS: &[&str] = &[
+    "Dedicated_IP",
+    "Double_VPN",
+    "Obfuscated_Servers",
+    "Onion_Over_VPN",
+    "P2P",
+    "Standard_VPN_Servers",
+];
+
+#[derive(Debug)]
+struct Connection {
+    location: Location,
+    country: String,
+    city: Option<String>,
+    since: Instant,
+}
+
+#[derive(Debug)]
+struct FakeState {
+    connected: Option<Connection>,
+    settings: Settings,
+    logged_in: bool,
+}
+
+impl Default for FakeState {
+    fn default() -> Self {
+        let toggles = Toggle::ALL
+            .into_iter()
+            .filter(|t| *t != Toggle::Obfuscate)
+            .map(|t| (t, t == Toggle::Firewall))
+            .collect();
+
+        Self {
+            connected: None,
+            logged_in: true,
+            settings: Settings {
+                technology: Some(Technology::NordLynx),
+                protocol: None,
+                toggles,
+            },
+        }
+    }
+}
+
+/// In-memory backend used for tests and for running the TUI without
+/// NordVPN installed.
+///
+/// Clones share the same state, so a test can keep a handle and inspect
+/// what the `App` did with it.
+#[derive(Debug, Clone)]
+pub struct FakeBackend {
+    countries: Arc<Vec<(String, Vec<String>)>>,
+    state: Arc<Mutex<FakeState>>,
+    delay: Duration,
+}
+
+impl Default for FakeBackend {
+    fn default() -> Self {
+        Self::new(SAMPLE)
+    }
+}
+
+impl FakeBackend {
+    pub fn new(countries: &[(&str, &[&str])]) -> Self {
+        Self {
+            countries: Arc::new(
+                countries
+                    .iter()
+                    .map(|(c, cities)| {
+                        (
+                            c.to_string(),
+                            cities.iter().map(|s| s.to_string()).collect(),
+                        )
+                    })
+                    .collect(),
+            ),
+            state: Arc::default(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Makes `connect` and `disconnect` take `delay`, like the real CLI does.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Logs in or out; while logged out `connect` fails like the real CLI.
+    #[allow(dead_code)]
+    pub fn set_logged_in(&self, logged_in: bool) {
+        self.state.lock().unwrap().logged_in = logged_in;
+    }
+
+    #[allow(dead_code)]
+    pub fn connected_to(&self) -> Option<Location> {
+        self.state
+            .lock()
+            .unwrap()
+            .connected
+            .as_ref()
+            .map(|c| c.location.clone())
+    }
+
+    fn wait(&self, cancel: &CancelToken) -> Result<(), AppError> {
+        let start = Instant::now();
+        while start.elapsed() < self.delay {
+            if cancel.is_cancelled() {
+                return Err(AppError::Cancelled);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        Ok(())
+    }
+
+    /// Country and city a connect request would land in, `None` if the
+    /// real CLI would reject it.
+    fn resolve(&self, location: &Location) -> Option<(String, Option<String>)> {
+        let country = |name: &str| {
+            self.countries
+                .iter()
+                .find(|(c, _)| c == name)
+                .map(|(c, cities)| (c.clone(), cities.first().cloned()))
+        };
+
+        match location {
+            Location::Recommended => self.countries.first().and_then(|(c, _)| country(c)),
+            // Hostnames use the first two letters of the country, see `status`.
+            Location::Server(server) => self
+                .countries
+                .iter()
+                .find(|(c, _)| {
+                    c.get(..2)
+                        .is_some_and(|code| server.starts_with(&code.to_lowercase()))
+                })
+                .map(|(c, _)| (c.clone(), None)),
+            Location::Country(name) => country(name),
+            Location::City(name) => country(name).or_else(|| {
+                self.countries.iter().find_map(|(c, cities)| {
+                    cities
+                        .iter()
+                        .find(|city| *city == name)
+                        .map(|city| (c.clone(), Some(city.clone())))
+                })
+            }),
+            Location::Group { group, country: c } => {
+                if !GROUPS.contains(&group.as_str()) {
+                    return None;
+                }
+                match c {
+                    Some(c) => country(c),
+                    None => self.countries.first().and_then(|(c, _)| country(c)),
+                }
+            }
+        }
+    }
+}
+
+fn output(code: i32, stdout: &str) -> CommandOutput {
+    CommandOutput {
+        status: ExitStatus::from_raw(code << 8),
+        stdout: stdout.to_string(),
+        stderr: String::new(),
+    }
+}
+
+impl Backend for FakeBackend {
+    fn countries(&self) -> Result<Vec<String>, AppError> {
+        Ok(self.countries.iter().map(|(c, _)| c.clone()).collect())
+    }
+
+    fn cities(&self, country: &str) -> Result<Vec<String>, AppError> {
+        Ok(self
+            .countries
+            .iter()
+            .find(|(c, _)| c == country)
+            .map(|(_, cities)| cities.clone())
+            .unwrap_or_default())
+    }
+
+    fn groups(&self) -> Result<Vec<String>, AppError> {
+        Ok(GROUPS.iter().map(|g| g.to_string()).collect())
+    }
+
+    fn status(&self) -> Result<Status, AppError> {
+        let state = self.state.lock().unwrap();
+        let Some(connection) = &state.connected else {
+            return Ok(Status::default());
+        };
+        let country = &connection.country;
+
+        Ok(Status {
+            state: State::Connected,
+            server: Some(format!("{} #42", country)),
+            hostname: Some(format!(
+                "{}42.nordvpn.com",
+                country.get(..2).unwrap_or(country).to_lowercase()
+            )),
+            ip: Some("10.5.0.2".to_string()),
+            country: Some(country.clone()),
+            city: connection.city.clone(),
+            technology: state.settings.technology.map(|t| t.to_string()),
+            protocol: state.settings.protocol.map(|p| p.to_string()),
+            transfer: Some(Default::default()),
+            uptime: Some(connection.since.elapsed()),
+        })
+    }
+
+    fn connect(
+        &self,
+        location: &Location,
+        cancel: &CancelToken,
+    ) -> Result<CommandOutput, AppError> {
+        self.wait(cancel)?;
+        if !self.state.lock().unwrap().logged_in {
+            return Ok(output(1, "You are not logged in.\n"));
+        }
+        let Some((country, city)) = self.resolve(location) else {
+            return Ok(output(
+                1,
+                "The specified server is not available at the moment or does not support your connection settings.\n",
+            ));
+        };
+        self.state.lock().unwrap().connected = Some(Connection {
+            location: location.clone(),
+            country,
+            city,
+            since: Instant::now(),
+        });
+        Ok(output(
+            0,
+            &format!(
+                "Connecting to {}\nYou are connected to {}!\n",
+                location, location
+            ),
+        ))
+    }
+
+    fn disconnect(&self, cancel: &CancelToken) -> Result<CommandOutput, AppError> {
+        self.wait(cancel)?;
+        let mut state = self.state.lock().unwrap();
+        if state.connected.take().is_none() {
+            return Ok(output(1, "You are not connected to NordVPN.\n"));
+        }
+        Ok(output(0, "You are disconnected from NordVPN.\n"))
+    }
+
+    fn settings(&self) -> Result<Settings, AppError> {
+        Ok(self.state.lock().unwrap().settings.clone())
+    }
+
+    fn preflight(&self) -> Vec<Check> {
+        let mut checks = vec![
+            Check::pass("nordvpn binary", "not needed, using sample data"),
+            Check::pass("CLI version", "fake backend"),
+            Check::pass("Daemon", "not needed"),
+            Check::pass("Permissions", "not needed"),
+        ];
+        checks.push(if self.state.lock().unwrap().logged_in {
+            Check::pass("Login", "logged in")
+        } else {
+            let error = NordVpnError::NotLoggedIn;
+            Check::fail("Login", error.to_string(), error.remedy())
+        });
+        checks
+    }
+
+    fn set(&self, change: Change) -> Result<CommandOutput, AppError> {
+        let mut state = self.state.lock().unwrap();
+        let settings = &mut state.settings;
+
+        let choice = match change {
+            Change::Toggle(toggle, enabled) => {
+                let state = if enabled { "enabled" } else { "disabled" };
+                return Ok(match settings.toggles.get_mut(&toggle) {
+                    Some(value) if *value == enabled => {
+                        output(1, &format!("{} is already set to '{}'.\n", toggle, state))
+                    }
+                    Some(value) => {
+                        *value = enabled;
+                        output(
+                            0,
+                            &format!("{} is set to '{}' successfully.\n", toggle, state),
+                        )
+                    }
+                    None => output(
+                        1,
+                        &format!("{} is not available with the current settings.\n", toggle),
+                    ),
+                });
+            }
+            Change::Technology(technology) => Choice::Technology(technology),
+            Change::Protocol(protocol) => Choice::Protocol(protocol),
+        };
+
+        if let Some(conflict) = choice.conflict(settings) {
+            return Ok(output(1, &format!("Cannot switch: {}.\n", conflict)));
+        }
+
+        match change {
+            Change::Technology(Technology::OpenVpn) => {
+                settings.technology = Some(Technology::OpenVpn);
+                settings.protocol = Some(Protocol::Udp);
+                settings.toggles.entry(Toggle::Obfuscate).or_insert(false);
+            }
+            Change::Technology(technology) => {
+                settings.technology = Some(technology);
+                settings.protocol = None;
+                settings.toggles.remove(&Toggle::Obfuscate);
+            }
+            Change::Protocol(protocol) => settings.protocol = Some(protocol),
+            Change::Toggle(..) => {}
+        }
+        Ok(output(
+            0,
+            &format!(
+                "{} is set to '{}' successfully.\n",
+                change.args()[1],
+                choice
+            ),
+        ))
+    }
+}
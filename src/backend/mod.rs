@@ -0,0 +1,70 @@
+use std::{
+    process::ExitStatus,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crate::{
+    data::Location,
+    doctor::Check,
+    error::AppError,
+    settings::{Change, Settings},
+    status::Status,
+};
+
+mod fake;
+mod nordvpn;
+
+pub use fake::FakeBackend;
+pub use nordvpn::NordVpnCli;
+
+/// Captured result of a backend command that changes the connection state.
+#[derive(Debug)]
+pub struct CommandOutput {
+    pub status: ExitStatus,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    pub fn lines(&self) -> Vec<String> {
+        self.stdout.lines().map(|s| s.to_string()).collect()
+    }
+}
+
+/// Shared flag used to abort a backend command that is still running.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Everything the TUI needs from a VPN provider.
+///
+/// `NordVpnCli` drives the real `nordvpn` binary, `FakeBackend` keeps
+/// everything in memory so the UI can run without NordVPN installed.
+///
+/// `connect` and `disconnect` run on a worker thread and must give up with
+/// `AppError::Cancelled` once `cancel` is set.
+pub trait Backend: std::fmt::Debug + Send + Sync {
+    fn countries(&self) -> Result<Vec<String>, AppError>;
+    fn cities(&self, country: &str) -> Result<Vec<String>, AppError>;
+    fn groups(&self) -> Result<Vec<String>, AppError>;
+    fn status(&self) -> Result<Status, AppError>;
+    fn connect(&self, location: &Location, cancel: &CancelToken)
+        -> Result<CommandOutput, AppError>;
+    fn disconnect(&self, cancel: &CancelToken) -> Result<CommandOutput, AppError>;
+    fn settings(&self) -> Result<Settings, AppError>;
+    fn set(&self, change: Change) -> Result<CommandOutput, AppError>;
+    /// Checks that the provider is usable, for `nordvpn-tui doctor`.
+    fn preflight(&self) -> Vec<Check>;
+}
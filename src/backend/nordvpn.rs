@@ -0,0 +1,306 @@
+use std::{
+    io::{ErrorKind, Read},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::Level;
+
+use crate::{
+    data::Location,
+    doctor::Check,
+    error::{AppError, NordVpnError},
+    settings::{Change, Settings},
+    status::{clean_line, Status},
+};
+
+use super::{Backend, CancelToken, CommandOutput};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Where `nordvpnd` listens for the CLI.
+const SOCKET: &str = "/run/nordvpn/nordvpnd.sock";
+
+/// Backend shelling out to the official `nordvpn` CLI.
+#[derive(Debug)]
+pub struct NordVpnCli {
+    program: String,
+}
+
+impl Default for NordVpnCli {
+    fn default() -> Self {
+        Self {
+            program: "nordvpn".to_string(),
+        }
+    }
+}
+
+impl NordVpnCli {
+    /// Runs a command to completion, logging it at `level` when it succeeds.
+    fn run(&self, args: &[&str], level: Level) -> Result<CommandOutput, AppError> {
+        let started = Instant::now();
+        let output = Command::new(&self.program)
+            .args(args)
+            .output()
+            .map_err(|e| self.spawn_error(args, e))?;
+
+        let output = CommandOutput {
+            status: output.status,
+            stdout: String::from_utf8(output.stdout)?,
+            stderr: String::from_utf8(output.stderr)?,
+        };
+        self.log_output(args, &output, started.elapsed(), level);
+        Ok(output)
+    }
+
+    /// Like `run`, but polls the child so it can be killed when `cancel` is set.
+    fn run_cancellable(
+        &self,
+        args: &[&str],
+        cancel: &CancelToken,
+    ) -> Result<CommandOutput, AppError> {
+        let started = Instant::now();
+        let mut child = Command::new(&self.program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| self.spawn_error(args, e))?;
+
+        let stdout = drain(child.stdout.take());
+        let stderr = drain(child.stderr.take());
+
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if cancel.is_cancelled() {
+                child.kill()?;
+                child.wait()?;
+                log::info!(
+                    "`{}` cancelled after {:.2?}",
+                    self.command_line(args),
+                    started.elapsed()
+                );
+                return Err(AppError::Cancelled);
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        let output = CommandOutput {
+            status,
+            stdout: String::from_utf8(stdout.join().unwrap_or_default())?,
+            stderr: String::from_utf8(stderr.join().unwrap_or_default())?,
+        };
+        self.log_output(args, &output, started.elapsed(), Level::Info);
+        Ok(output)
+    }
+
+    /// Where `program` would be run from, searching `PATH` for bare names.
+    fn find_program(&self) -> Option<PathBuf> {
+        let program = Path::new(&self.program);
+        if program.components().count() > 1 {
+            return program.is_file().then(|| program.to_path_buf());
+        }
+        let paths = std::env::var_os("PATH")?;
+        std::env::split_paths(&paths)
+            .map(|dir| dir.join(program))
+            .find(|path| path.is_file())
+    }
+
+    fn check_login(&self) -> Check {
+        let output = match self.query(&["account"]) {
+            Ok(output) => output,
+            Err(e) => {
+                let remedy = e
+                    .remedy()
+                    .unwrap_or("Run `nordvpn account` to see what is wrong.");
+                return Check::fail("Login", e.to_string(), remedy);
+            }
+        };
+        // Some versions report this with a successful exit status.
+        if let Some(error @ NordVpnError::NotLoggedIn) = NordVpnError::detect(&output.stdout) {
+            return Check::fail("Login", error.to_string(), error.remedy());
+        }
+        let email = output.stdout.lines().find_map(|line| {
+            let line = clean_line(line);
+            let (key, value) = line.split_once(':')?;
+            key.contains("Email").then(|| value.trim().to_string())
+        });
+        match email {
+            Some(email) => Check::pass("Login", format!("logged in as {}", email)),
+            None => Check::pass("Login", "logged in"),
+        }
+    }
+
+    fn command_line(&self, args: &[&str]) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(args.iter().copied())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn spawn_error(&self, args: &[&str], error: std::io::Error) -> AppError {
+        log::error!("could not run `{}`: {}", self.command_line(args), error);
+        match error.kind() {
+            ErrorKind::NotFound => AppError::NordVpn(NordVpnError::NotInstalled, None),
+            _ => AppError::Io(error),
+        }
+    }
+
+    /// Failures are always logged with their output; successful commands
+    /// only at `level`, with the output one level below.
+    fn log_output(&self, args: &[&str], output: &CommandOutput, elapsed: Duration, level: Level) {
+        let (level, output_level) = if output.status.success() {
+            let output_level = match level {
+                Level::Error | Level::Warn | Level::Info => Level::Debug,
+                Level::Debug | Level::Trace => Level::Trace,
+            };
+            (level, output_level)
+        } else {
+            (Level::Warn, Level::Warn)
+        };
+        log::log!(
+            level,
+            "`{}` exited with {} after {:.2?}",
+            self.command_line(args),
+            output.status,
+            elapsed
+        );
+        for (name, text) in [("stdout", &output.stdout), ("stderr", &output.stderr)] {
+            let text = text.lines().map(clean_line).collect::<Vec<_>>().join("\n");
+            let text = text.trim();
+            if !text.is_empty() {
+                log::log!(output_level, "{}:\n{}", name, text);
+            }
+        }
+    }
+
+    /// Runs a command that only reads, failing when it does.
+    fn query(&self, args: &[&str]) -> Result<CommandOutput, AppError> {
+        let output = self.run(args, Level::Debug)?;
+        if output.status.success() {
+            Ok(output)
+        } else {
+            Err(AppError::from_output(output))
+        }
+    }
+
+    fn words(&self, args: &[&str]) -> Result<Vec<String>, AppError> {
+        Ok(self
+            .query(args)?
+            .stdout
+            .split_whitespace()
+            .map(|s| s.to_string())
+            .collect())
+    }
+}
+
+/// Reads a child pipe to the end on its own thread so the child never
+/// blocks on a full pipe while we wait for it.
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+impl Backend for NordVpnCli {
+    fn countries(&self) -> Result<Vec<String>, AppError> {
+        self.words(&["countries"])
+    }
+
+    fn cities(&self, country: &str) -> Result<Vec<String>, AppError> {
+        self.words(&["cities", country])
+    }
+
+    fn groups(&self) -> Result<Vec<String>, AppError> {
+        self.words(&["groups"])
+    }
+
+    fn status(&self) -> Result<Status, AppError> {
+        Ok(Status::parse(&self.query(&["status"])?.stdout))
+    }
+
+    fn connect(
+        &self,
+        location: &Location,
+        cancel: &CancelToken,
+    ) -> Result<CommandOutput, AppError> {
+        let mut args = vec!["connect"];
+        args.extend(location.args());
+        self.run_cancellable(&args, cancel)
+    }
+
+    fn disconnect(&self, cancel: &CancelToken) -> Result<CommandOutput, AppError> {
+        self.run_cancellable(&["disconnect"], cancel)
+    }
+
+    fn settings(&self) -> Result<Settings, AppError> {
+        Ok(Settings::parse(&self.query(&["settings"])?.stdout))
+    }
+
+    fn set(&self, change: Change) -> Result<CommandOutput, AppError> {
+        self.run(&change.args(), Level::Info)
+    }
+
+    fn preflight(&self) -> Vec<Check> {
+        let Some(path) = self.find_program() else {
+            let error = NordVpnError::NotInstalled;
+            let mut checks = vec![Check::fail(
+                "nordvpn binary",
+                format!("`{}` not found on PATH", self.program),
+                error.remedy(),
+            )];
+            for name in ["CLI version", "Daemon", "Permissions", "Login"] {
+                checks.push(Check::skip(name, "needs the nordvpn binary"));
+            }
+            return checks;
+        };
+        let mut checks = vec![Check::pass("nordvpn binary", path.display().to_string())];
+
+        checks.push(match self.query(&["--version"]) {
+            Ok(output) => Check::pass("CLI version", output.stdout.trim()),
+            Err(e) => Check::warn(
+                "CLI version",
+                e.to_string(),
+                "Reinstall the NordVPN client if the other checks fail too.",
+            ),
+        });
+
+        match UnixStream::connect(SOCKET) {
+            Ok(_) => {
+                checks.push(Check::pass("Daemon", format!("listening on {}", SOCKET)));
+                checks.push(Check::pass("Permissions", "allowed to talk to the daemon"));
+                checks.push(self.check_login());
+            }
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+                let error = NordVpnError::PermissionDenied;
+                checks.push(Check::pass("Daemon", format!("listening on {}", SOCKET)));
+                checks.push(Check::fail(
+                    "Permissions",
+                    error.to_string(),
+                    error.remedy(),
+                ));
+                checks.push(Check::skip("Login", "needs access to the daemon"));
+            }
+            Err(e) => {
+                let error = NordVpnError::DaemonNotRunning;
+                checks.push(Check::fail(
+                    "Daemon",
+                    format!("cannot reach {}: {}", SOCKET, e),
+                    error.remedy(),
+                ));
+                checks.push(Check::skip("Permissions", "needs the daemon"));
+                checks.push(Check::skip("Login", "needs the daemon"));
+            }
+        }
+        checks
+    }
+}
@@ -1,4 +1,6 @@
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::colors::ColorSupport;
 
 #[derive(Debug, Parser)]
 pub struct Cli {
@@ -6,7 +8,67 @@ pub struct Cli {
     #[arg(short, long)]
     pub config: Option<String>,
 
-    /// Verbosity level
+    /// Write more to the log file: -v info, -vv debug, -vvv trace
     #[arg(short, long, action = clap::ArgAction::Count)]
     pub verbose: u8,
+
+    /// VPN backend to drive
+    #[arg(long, value_enum, default_value_t = BackendKind::Nordvpn)]
+    pub backend: BackendKind,
+
+    /// Colors the terminal supports, detected from NO_COLOR, COLORTERM and TERM by default
+    #[arg(long, value_enum)]
+    pub colors: Option<ColorSupport>,
+
+    /// Print machine-readable JSON instead of text (subcommands only)
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Run a single command and exit instead of starting the interface
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Show the connection status; exits with 3 when not connected
+    Status {
+        /// Print one line from a template instead, e.g. '{country} {city} {ip}'
+        #[arg(long)]
+        format: Option<String>,
+        /// Keep running and print a waybar JSON line whenever the status changes
+        #[arg(long)]
+        watch: bool,
+        /// Seconds between status checks in watch mode
+        #[arg(
+            long,
+            default_value_t = 5,
+            requires = "watch",
+            value_parser = clap::value_parser!(u64).range(1..)
+        )]
+        interval: u64,
+    },
+    /// Connect to a country, or to a city within it
+    Connect {
+        country: String,
+        city: Option<String>,
+    },
+    /// Disconnect from the VPN
+    Disconnect,
+    /// List the countries with servers
+    Countries,
+    /// List the cities of a country
+    Cities { country: String },
+    /// List the saved favorites
+    Favorites,
+    /// Check that NordVPN and the config are usable; exits with 1 if not
+    Doctor,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum BackendKind {
+    /// The `nordvpn` command-line client
+    Nordvpn,
+    /// In-memory sample data, no NordVPN installation required
+    Fake,
 }
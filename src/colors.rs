@@ -0,0 +1,142 @@
+use clap::ValueEnum;
+use ratatui::style::Color;
+
+/// The 16 ANSI colors with the RGB values xterm uses for them.
+const ANSI: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Channel levels of the 6x6x6 cube in the 256-color palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// How many colors the terminal can show.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorSupport {
+    /// 24-bit RGB
+    #[default]
+    #[value(name = "truecolor")]
+    TrueColor,
+    /// The xterm 256-color palette
+    #[value(name = "256")]
+    Ansi256,
+    /// The 16 ANSI colors
+    #[value(name = "16")]
+    Ansi16,
+    /// No colors, only bold, reverse and underline
+    #[value(name = "none")]
+    Monochrome,
+}
+
+impl ColorSupport {
+    /// Guesses the support from `NO_COLOR`, `COLORTERM` and `TERM`.
+    pub fn detect() -> Self {
+        let var = |name| std::env::var(name).ok();
+        Self::from_env(
+            var("NO_COLOR").as_deref(),
+            var("COLORTERM").as_deref(),
+            var("TERM").as_deref(),
+        )
+    }
+
+    pub fn from_env(no_color: Option<&str>, colorterm: Option<&str>, term: Option<&str>) -> Self {
+        // https://no-color.org: any non-empty value disables colors.
+        if no_color.is_some_and(|v| !v.is_empty()) {
+            return ColorSupport::Monochrome;
+        }
+        if let Some("truecolor" | "24bit") = colorterm {
+            return ColorSupport::TrueColor;
+        }
+        match term {
+            Some("dumb") => ColorSupport::Monochrome,
+            Some(term) if term.contains("truecolor") || term.contains("direct") => {
+                ColorSupport::TrueColor
+            }
+            Some(term) if term.contains("256color") => ColorSupport::Ansi256,
+            _ => ColorSupport::Ansi16,
+        }
+    }
+
+    /// Maps `color` to the closest one the terminal can show.
+    pub fn downgrade(&self, color: Color) -> Color {
+        match (self, color) {
+            (ColorSupport::Monochrome, _) => Color::Reset,
+            (ColorSupport::TrueColor, color) => color,
+            (ColorSupport::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_256(r, g, b)),
+            (ColorSupport::Ansi16, Color::Rgb(r, g, b)) => nearest_16((r, g, b)),
+            (ColorSupport::Ansi16, Color::Indexed(i)) => nearest_16(indexed_rgb(i)),
+            (_, color) => color,
+        }
+    }
+
+    pub fn is_monochrome(&self) -> bool {
+        *self == ColorSupport::Monochrome
+    }
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+fn nearest_16(rgb: (u8, u8, u8)) -> Color {
+    ANSI.iter()
+        .min_by_key(|(_, ansi)| distance(rgb, *ansi))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
+
+/// Index of the closest entry in the color cube or the grayscale ramp.
+fn nearest_256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |v: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - v as i32).abs())
+            .unwrap_or_default()
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+
+    // The ramp goes 8, 18, ..., 238 over indexes 232-255.
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray = 8 + step * 10;
+
+    if distance((r, g, b), (gray, gray, gray)) < distance((r, g, b), cube) {
+        232 + step
+    } else {
+        16 + 36 * ri as u8 + 6 * gi as u8 + bi as u8
+    }
+}
+
+/// RGB value of an entry of the 256-color palette.
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI[index as usize].1,
+        16..=231 => {
+            let i = index - 16;
+            (
+                CUBE_LEVELS[(i / 36) as usize],
+                CUBE_LEVELS[(i / 6 % 6) as usize],
+                CUBE_LEVELS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
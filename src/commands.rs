@@ -0,0 +1,315 @@
+use std::{
+    io::Write,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    backend::{Backend, CancelToken},
+    cli::Command,
+    data::Location,
+    doctor,
+    error::{AppError, NordVpnError, EXIT_FAILURE},
+    favorites::Favorites,
+    history::{Entry, History, Outcome},
+    status::{clean_line, Status},
+    task::Operation,
+};
+
+pub const EXIT_OK: i32 = 0;
+/// `status` only: the VPN is not connected.
+pub const EXIT_DISCONNECTED: i32 = 3;
+
+/// Runs the subcommands of `nordvpn-tui` without starting the interface.
+#[derive(Debug)]
+pub struct Runner {
+    backend: Arc<dyn Backend>,
+    favorites: Favorites,
+    history: History,
+    /// `--config` as given, for `doctor`.
+    config: Option<String>,
+    json: bool,
+}
+
+impl Runner {
+    pub fn new(backend: Arc<dyn Backend>, json: bool) -> Self {
+        Self {
+            backend,
+            favorites: Favorites::default(),
+            history: History::default(),
+            config: None,
+            json,
+        }
+    }
+
+    pub fn with_favorites(mut self, favorites: Favorites) -> Self {
+        self.favorites = favorites;
+        self
+    }
+
+    pub fn with_history(mut self, history: History) -> Self {
+        self.history = history;
+        self
+    }
+
+    pub fn with_config(mut self, config: Option<String>) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Runs `command`, printing to `out`, and returns the exit code. With
+    /// `--json`, errors are printed as JSON too.
+    pub fn run(&mut self, command: Command, out: &mut dyn Write) -> Result<i32, AppError> {
+        match self.execute(command, out) {
+            Err(e) if self.json && !matches!(e, AppError::Io(_)) => {
+                print_json(out, &error_json(&e))?;
+                Ok(e.exit_code())
+            }
+            result => result,
+        }
+    }
+
+    fn execute(&mut self, command: Command, out: &mut dyn Write) -> Result<i32, AppError> {
+        match command {
+            Command::Status {
+                watch: true,
+                format,
+                interval,
+            } => self.watch(format.as_deref(), Duration::from_secs(interval), out),
+            Command::Status { format, .. } => {
+                let status = self.backend.status()?;
+                if let Some(template) = format {
+                    writeln!(out, "{}", status.render(&template))?;
+                } else if self.json {
+                    print_json(out, &status_json(&status))?;
+                } else {
+                    for (label, value) in status.rows(Duration::ZERO) {
+                        writeln!(out, "{}: {}", label, value)?;
+                    }
+                }
+                Ok(if status.is_connected() {
+                    EXIT_OK
+                } else {
+                    EXIT_DISCONNECTED
+                })
+            }
+            Command::Connect { country, city } => {
+                let location = match city {
+                    Some(city) => Location::City(self.city_in(&country, &city)?),
+                    None => Location::Country(country),
+                };
+                self.change(Operation::Connect(location), out)
+            }
+            Command::Disconnect => self.change(Operation::Disconnect, out),
+            Command::Countries => self.list(&self.backend.countries()?, out),
+            Command::Cities { country } => self.list(&self.backend.cities(&country)?, out),
+            Command::Favorites => {
+                let items = self.favorites.items();
+                if self.json {
+                    print_json(out, &items)?;
+                } else {
+                    for favorite in items {
+                        writeln!(out, "{}", favorite)?;
+                    }
+                }
+                Ok(EXIT_OK)
+            }
+            Command::Doctor => {
+                let checks = doctor::run(self.backend.as_ref(), self.config.as_deref());
+                let passed = doctor::passed(&checks);
+                if self.json {
+                    print_json(out, &json!({ "passed": passed, "checks": checks }))?;
+                } else {
+                    for check in &checks {
+                        writeln!(out, "{}", check)?;
+                        if let Some(remedy) = check.remedy {
+                            writeln!(out, "    {}", remedy)?;
+                        }
+                    }
+                }
+                Ok(if passed { EXIT_OK } else { EXIT_FAILURE })
+            }
+        }
+    }
+
+    /// Connects or disconnects, waiting for NordVPN to finish.
+    fn change(&mut self, operation: Operation, out: &mut dyn Write) -> Result<i32, AppError> {
+        let started = Instant::now();
+        let cancel = CancelToken::default();
+        let result = match &operation {
+            Operation::Connect(location) => self.backend.connect(location, &cancel),
+            Operation::Disconnect => self.backend.disconnect(&cancel),
+            Operation::Set(change) => self.backend.set(*change),
+        };
+
+        if let Operation::Connect(location) = &operation {
+            let outcome = match &result {
+                Ok(output) if output.status.success() => Outcome::Connected,
+                _ => Outcome::Failed,
+            };
+            // The connection is what was asked for; the history is a bonus.
+            if let Err(e) =
+                self.history
+                    .record(Entry::new(location.clone(), outcome, started.elapsed()))
+            {
+                log::warn!("could not record the connection history: {}", e);
+            }
+        }
+
+        let output = result?;
+        if !output.status.success() {
+            return Err(AppError::from_output(output));
+        }
+        let lines: Vec<String> = output
+            .stdout
+            .lines()
+            .map(clean_line)
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        if self.json {
+            let location = match &operation {
+                Operation::Connect(location) => Some(location),
+                _ => None,
+            };
+            print_json(
+                out,
+                &json!({
+                    "operation": operation.to_string(),
+                    "location": location,
+                    "success": true,
+                    "output": lines,
+                }),
+            )?;
+        } else {
+            for line in &lines {
+                writeln!(out, "{}", line)?;
+            }
+        }
+
+        Ok(EXIT_OK)
+    }
+
+    /// Prints a waybar line now and whenever its text or class changes,
+    /// until `out` is closed. The tooltip's uptime and transfer move on
+    /// every poll, so they alone do not count as a change.
+    fn watch(
+        &self,
+        format: Option<&str>,
+        interval: Duration,
+        out: &mut dyn Write,
+    ) -> Result<i32, AppError> {
+        let mut last = None;
+        loop {
+            let line = waybar_json(&self.backend.status(), format);
+            let shown = (line["text"].clone(), line["class"].clone());
+            if last.as_ref() != Some(&shown) {
+                writeln!(out, "{}", serde_json::to_string(&line)?)?;
+                out.flush()?;
+                last = Some(shown);
+            }
+            thread::sleep(interval);
+        }
+    }
+
+    /// `city` as NordVPN spells it, as long as it lies in `country`;
+    /// NordVPN itself would connect to a namesake anywhere.
+    fn city_in(&self, country: &str, city: &str) -> Result<String, AppError> {
+        let key = |name: &str| name.replace(' ', "_").to_lowercase();
+        self.backend
+            .cities(country)?
+            .into_iter()
+            .find(|c| key(c) == key(city))
+            .ok_or(AppError::NordVpn(NordVpnError::UnknownLocation, None))
+    }
+
+    fn list(&self, items: &[String], out: &mut dyn Write) -> Result<i32, AppError> {
+        if self.json {
+            print_json(out, &items)?;
+        } else {
+            for item in items {
+                writeln!(out, "{}", item)?;
+            }
+        }
+        Ok(EXIT_OK)
+    }
+}
+
+fn print_json<T: Serialize + ?Sized>(out: &mut dyn Write, value: &T) -> Result<(), AppError> {
+    serde_json::to_writer_pretty(&mut *out, value)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// A line of waybar's custom module protocol, which polybar, i3blocks and
+/// tmux scripts can pick apart as well.
+pub fn waybar_json(status: &Result<Status, AppError>, format: Option<&str>) -> serde_json::Value {
+    match status {
+        Ok(status) => {
+            let text = match format {
+                Some(template) => status.render(template),
+                None if status.is_connected() => status.render("{location}"),
+                None => status.state.to_string(),
+            };
+            let tooltip: Vec<String> = status
+                .rows(Duration::ZERO)
+                .into_iter()
+                .map(|(label, value)| format!("{}: {}", label, value))
+                .collect();
+            let class = if status.is_connected() {
+                "connected"
+            } else {
+                "disconnected"
+            };
+            json!({ "text": text, "tooltip": tooltip.join("\n"), "class": class })
+        }
+        Err(e) => json!({ "text": "VPN error", "tooltip": e.to_string(), "class": "error" }),
+    }
+}
+
+fn error_json(error: &AppError) -> serde_json::Value {
+    let kind = match error {
+        AppError::NordVpn(kind, _) => kind.name(),
+        AppError::Command(_) => "rejected",
+        _ => "failed",
+    };
+    let output: Vec<String> = error
+        .output()
+        .map(|output| {
+            output
+                .stdout
+                .lines()
+                .chain(output.stderr.lines())
+                .map(clean_line)
+                .filter(|line| !line.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    json!({
+        "success": false,
+        "error": kind,
+        "message": error.to_string(),
+        "remedy": error.remedy(),
+        "exit_code": error.exit_code(),
+        "output": output,
+    })
+}
+
+fn status_json(status: &Status) -> serde_json::Value {
+    json!({
+        "state": status.state.to_string().to_lowercase(),
+        "connected": status.is_connected(),
+        "server": status.hostname.as_ref().or(status.server.as_ref()),
+        "ip": status.ip,
+        "country": status.country,
+        "city": status.city,
+        "technology": status.technology,
+        "protocol": status.protocol,
+        "transfer": status.transfer.map(|t| json!({ "received": t.received, "sent": t.sent })),
+        "uptime": status.uptime.map(|u| u.as_secs()),
+    })
+}
@@ -1,61 +1,369 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
 use ratatui::style::Color;
 use serde::Deserialize;
 
-#[derive(Debug, Deserialize, Default)]
+use crate::{colors::ColorSupport, keys::Keymap, paths};
+
+/// Colors of the interface, as resolved from the theme and `[colors]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ColorsConfig {
-    #[serde(deserialize_with = "crate::config::hex_to_rgb")]
     pub connected: Color,
-    #[serde(deserialize_with = "crate::config::hex_to_rgb")]
     pub disconnected: Color,
-    #[serde(deserialize_with = "crate::config::hex_to_rgb")]
     pub items: Color,
-    #[serde(deserialize_with = "crate::config::hex_to_rgb")]
     pub items_selected: Color,
-    #[serde(deserialize_with = "crate::config::hex_to_rgb")]
     pub normal_mode: Color,
-    #[serde(deserialize_with = "crate::config::hex_to_rgb")]
     pub search_mode: Color,
-    #[serde(deserialize_with = "crate::config::hex_to_rgb")]
     pub connection_output: Color,
-    #[serde(deserialize_with = "crate::config::hex_to_rgb")]
     pub background: Color,
 }
 
+impl ColorsConfig {
+    /// The same colors, mapped to what a terminal with `support` can show.
+    pub fn downgrade(&self, support: ColorSupport) -> Self {
+        Self {
+            connected: support.downgrade(self.connected),
+            disconnected: support.downgrade(self.disconnected),
+            items: support.downgrade(self.items),
+            items_selected: support.downgrade(self.items_selected),
+            normal_mode: support.downgrade(self.normal_mode),
+            search_mode: support.downgrade(self.search_mode),
+            connection_output: support.downgrade(self.connection_output),
+            background: support.downgrade(self.background),
+        }
+    }
+}
+
+impl Default for ColorsConfig {
+    fn default() -> Self {
+        Theme::default().palette()
+    }
+}
+
+/// Built-in palettes selected with `theme = "..."`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Dracula,
+    Nord,
+    Gruvbox,
+    Solarized,
+}
+
+impl Theme {
+    pub fn palette(&self) -> ColorsConfig {
+        let rgb = |hex: u32| Color::from_u32(hex);
+        match self {
+            Theme::Dracula => ColorsConfig {
+                connected: rgb(0x50fa7b),
+                disconnected: rgb(0xff5555),
+                items: rgb(0x6272a4),
+                items_selected: rgb(0xff79c6),
+                normal_mode: rgb(0xf8f8f2),
+                search_mode: rgb(0xf1fa8c),
+                connection_output: rgb(0xf8f8f2),
+                background: rgb(0x1e1f35),
+            },
+            Theme::Nord => ColorsConfig {
+                connected: rgb(0xa3be8c),
+                disconnected: rgb(0xbf616a),
+                items: rgb(0x81a1c1),
+                items_selected: rgb(0x88c0d0),
+                normal_mode: rgb(0xeceff4),
+                search_mode: rgb(0xebcb8b),
+                connection_output: rgb(0xd8dee9),
+                background: rgb(0x2e3440),
+            },
+            Theme::Gruvbox => ColorsConfig {
+                connected: rgb(0xb8bb26),
+                disconnected: rgb(0xfb4934),
+                items: rgb(0x83a598),
+                items_selected: rgb(0xfe8019),
+                normal_mode: rgb(0xebdbb2),
+                search_mode: rgb(0xfabd2f),
+                connection_output: rgb(0xebdbb2),
+                background: rgb(0x282828),
+            },
+            Theme::Solarized => ColorsConfig {
+                connected: rgb(0x859900),
+                disconnected: rgb(0xdc322f),
+                items: rgb(0x268bd2),
+                items_selected: rgb(0xd33682),
+                normal_mode: rgb(0x93a1a1),
+                search_mode: rgb(0xb58900),
+                connection_output: rgb(0x839496),
+                background: rgb(0x002b36),
+            },
+        }
+    }
+}
+
+/// The `[colors]` table: each entry set here replaces the theme's color.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ColorsOverrides {
+    #[serde(deserialize_with = "crate::config::color")]
+    connected: Option<Color>,
+    #[serde(deserialize_with = "crate::config::color")]
+    disconnected: Option<Color>,
+    #[serde(deserialize_with = "crate::config::color")]
+    items: Option<Color>,
+    #[serde(deserialize_with = "crate::config::color")]
+    items_selected: Option<Color>,
+    #[serde(deserialize_with = "crate::config::color")]
+    normal_mode: Option<Color>,
+    #[serde(deserialize_with = "crate::config::color")]
+    search_mode: Option<Color>,
+    #[serde(deserialize_with = "crate::config::color")]
+    connection_output: Option<Color>,
+    #[serde(deserialize_with = "crate::config::color")]
+    background: Option<Color>,
+}
+
+impl ColorsOverrides {
+    fn apply(self, colors: &mut ColorsConfig) {
+        let fields = [
+            (self.connected, &mut colors.connected),
+            (self.disconnected, &mut colors.disconnected),
+            (self.items, &mut colors.items),
+            (self.items_selected, &mut colors.items_selected),
+            (self.normal_mode, &mut colors.normal_mode),
+            (self.search_mode, &mut colors.search_mode),
+            (self.connection_output, &mut colors.connection_output),
+            (self.background, &mut colors.background),
+        ];
+        for (value, field) in fields {
+            if let Some(value) = value {
+                *field = value;
+            }
+        }
+    }
+}
+
+/// `config.toml` as written; turned into a `Config` once the theme is known.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RawConfig {
+    theme: Theme,
+    colors: ColorsOverrides,
+    keys: Keymap,
+}
+
+impl From<RawConfig> for Config {
+    fn from(raw: RawConfig) -> Self {
+        let mut colors = raw.theme.palette();
+        raw.colors.apply(&mut colors);
+        Self {
+            colors,
+            keys: raw.keys,
+            warnings: Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Default)]
+#[serde(from = "RawConfig")]
 pub struct Config {
     pub colors: ColorsConfig,
+    pub keys: Keymap,
+    /// Problems found while loading that did not stop it, e.g. unknown keys.
+    pub warnings: Vec<String>,
 }
 
 impl Config {
+    /// `$XDG_CONFIG_HOME/nordvpn-tui/config.toml`.
+    pub fn default_path() -> PathBuf {
+        paths::config_dir().join("config.toml")
+    }
+
+    /// Loads `path`, or the default path when `None`.
+    ///
+    /// A missing file gives the built-in defaults; unknown keys are skipped
+    /// and reported in `warnings`.
     pub fn load(path: Option<&str>) -> Result<Self, std::io::Error> {
-        let config = if let Some(p) = path {
-            std::fs::read_to_string(p)?
-        } else {
-            let mut p = std::env::var("HOME").unwrap_or("./".to_string());
-            p.push_str("/.config/nordvpn-tui/config.toml");
-            std::fs::read_to_string(p)?
+        Self::load_or(path, Self::default_path())
+    }
+
+    /// Like `load`, with `default` standing in for the default path.
+    pub fn load_or(path: Option<&str>, default: PathBuf) -> Result<Self, std::io::Error> {
+        let file = path.map(PathBuf::from).unwrap_or(default);
+        let content = match std::fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let mut config = Config::default();
+                // Only worth mentioning when the file was asked for.
+                if path.is_some() {
+                    config
+                        .warnings
+                        .push(format!("{} not found, using defaults", file.display()));
+                }
+                return Ok(config);
+            }
+            Err(e) => return Err(e),
         };
-        toml::from_str(&config).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        Self::parse(&content).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
     }
+
+    pub fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        let mut unknown = Vec::new();
+        let mut config: Config =
+            serde_ignored::deserialize(toml::Deserializer::new(content), |path| {
+                unknown.push(format!("unknown key `{}` ignored", path))
+            })?;
+        config.warnings = unknown;
+        Ok(config)
+    }
+}
+
+/// Reloads the config file when it changes on disk, checked every `interval`
+/// from the event loop.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    /// Path given on the command line, `None` for the default one.
+    arg: Option<String>,
+    path: PathBuf,
+    /// Modification time and size when last seen, `None` while missing.
+    seen: Option<(SystemTime, u64)>,
+    interval: Duration,
+    next: Instant,
+}
+
+impl ConfigWatcher {
+    pub fn new(arg: Option<&str>, interval: Duration) -> Self {
+        let path = arg.map(PathBuf::from).unwrap_or_else(Config::default_path);
+        Self {
+            arg: arg.map(str::to_string),
+            seen: fingerprint(&path),
+            path,
+            interval,
+            next: Instant::now() + interval,
+        }
+    }
+
+    /// The `--config` argument this watches.
+    pub fn arg(&self) -> Option<&str> {
+        self.arg.as_deref()
+    }
+
+    /// The reloaded config if the file changed since the last check, or the
+    /// reason it could not be loaded.
+    pub fn poll(&mut self) -> Option<Result<Config, String>> {
+        if Instant::now() < self.next {
+            return None;
+        }
+        self.next = Instant::now() + self.interval;
+
+        let seen = fingerprint(&self.path);
+        if seen == self.seen {
+            return None;
+        }
+        self.seen = seen;
+        Some(Config::load(self.arg.as_deref()).map_err(|e| summarize(&e.to_string())))
+    }
+}
+
+fn fingerprint(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
 }
 
-fn hex_to_rgb<'de, D>(deserializer: D) -> Result<Color, D::Error>
+/// Squeezes a multi-line TOML error, which quotes the offending line, into
+/// `TOML parse error at line 2, column 9: Invalid hex color: #12`.
+pub fn summarize(error: &str) -> String {
+    let mut lines = error.lines().map(str::trim).filter(|l| !l.is_empty());
+    match (lines.next(), lines.next_back()) {
+        (Some(first), Some(last)) => format!("{}: {}", first, last),
+        (Some(first), None) => first.to_string(),
+        _ => error.to_string(),
+    }
+}
+
+/// Parses `#rrggbb`, `rrggbb`, `#rgb`, a 256-color index, an ANSI color
+/// name such as `red` or `lightblue`, or `reset` for the terminal default.
+pub fn parse_color(value: &str) -> Result<Color, String> {
+    let value = value.trim();
+    let hex = value.strip_prefix('#');
+
+    let digits = hex.unwrap_or(value);
+    let is_hex = digits.chars().all(|c| c.is_ascii_hexdigit());
+    match (hex.is_some(), digits.len()) {
+        (_, 6) if is_hex => {
+            return Ok(Color::from_u32(
+                u32::from_str_radix(digits, 16).map_err(|e| e.to_string())?,
+            ))
+        }
+        // Three digits only with `#`: `add` or `bad` would be names otherwise.
+        (true, 3) if is_hex => {
+            let channel = |i: usize| {
+                u8::from_str_radix(&digits[i..=i], 16)
+                    .map(|v| v * 0x11)
+                    .unwrap_or_default()
+            };
+            return Ok(Color::Rgb(channel(0), channel(1), channel(2)));
+        }
+        (true, _) => return Err(format!("Invalid hex color: {}", value)),
+        _ => {}
+    }
+
+    if let Ok(index) = value.parse::<u8>() {
+        return Ok(Color::Indexed(index));
+    }
+
+    let name: String = value
+        .chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' '))
+        .collect::<String>()
+        .to_lowercase();
+    Ok(match name.as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return Err(format!("Invalid color: {}", value)),
+    })
+}
+
+/// Deserializes a color given either as a string or as a bare 256-color index.
+fn color<'de, D>(deserializer: D) -> Result<Option<Color>, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    let hex_str: String = String::deserialize(deserializer)?;
-    let hex = hex_str.trim_start_matches('#');
+    struct ColorVisitor;
 
-    if hex.len() != 6 {
-        return Err(serde::de::Error::custom(format!(
-            "Invalid hex color: {}",
-            hex_str
-        )));
-    }
+    impl serde::de::Visitor<'_> for ColorVisitor {
+        type Value = Color;
 
-    let r = u8::from_str_radix(&hex[0..2], 16).map_err(serde::de::Error::custom)?;
-    let g = u8::from_str_radix(&hex[2..4], 16).map_err(serde::de::Error::custom)?;
-    let b = u8::from_str_radix(&hex[4..6], 16).map_err(serde::de::Error::custom)?;
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "a hex color, a color name or a 256-color index")
+        }
+
+        fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Color, E> {
+            parse_color(value).map_err(E::custom)
+        }
+
+        fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<Color, E> {
+            u8::try_from(value)
+                .map(Color::Indexed)
+                .map_err(|_| E::custom(format!("Invalid color index: {}", value)))
+        }
+    }
 
-    Ok(Color::Rgb(r, g, b))
+    deserializer.deserialize_any(ColorVisitor).map(Some)
 }
@@ -28,3 +28,76 @@ impl Country {
         }
     }
 }
+
+/// Where a connect request should go.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Location {
+    /// Plain `nordvpn connect`, the server NordVPN recommends.
+    Recommended,
+    /// A specific server such as `de1234`.
+    Server(String),
+    Country(String),
+    City(String),
+    Group {
+        group: String,
+        country: Option<String>,
+    },
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Location::Recommended => write!(f, "recommended server"),
+            Location::Server(server) => write!(f, "{}", server),
+            Location::Country(country) => write!(f, "{}", country),
+            Location::City(city) => write!(f, "{}", city),
+            Location::Group {
+                group,
+                country: None,
+            } => write!(f, "{}", group),
+            Location::Group {
+                group,
+                country: Some(country),
+            } => write!(f, "{} ({})", country, group),
+        }
+    }
+}
+
+impl Location {
+    /// Arguments following `nordvpn connect`.
+    pub fn args(&self) -> Vec<&str> {
+        match self {
+            Location::Recommended => Vec::new(),
+            Location::Server(place) | Location::Country(place) | Location::City(place) => {
+                vec![place]
+            }
+            Location::Group { group, country } => {
+                let mut args = vec!["--group", group.as_str()];
+                args.extend(country.as_deref());
+                args
+            }
+        }
+    }
+}
+
+/// Normalizes a server name typed by the user, e.g. `DE1234` or
+/// `de1234.nordvpn.com` to `de1234`, or `us-ca10` for Double VPN pairs.
+///
+/// Returns `None` unless it looks like `<code>[-<code>]<number>` with
+/// two-letter country codes.
+pub fn parse_server(input: &str) -> Option<String> {
+    let name = input.trim().to_lowercase();
+    let name = name.strip_suffix(".nordvpn.com").unwrap_or(&name);
+
+    let digits = name.trim_start_matches(|c: char| c.is_ascii_lowercase() || c == '-');
+    let codes = &name[..name.len() - digits.len()];
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let mut parts = codes.split('-');
+    let valid = parts.clone().count() <= 2
+        && parts.all(|code| code.len() == 2 && code.chars().all(|c| c.is_ascii_lowercase()));
+
+    valid.then(|| name.to_string())
+}
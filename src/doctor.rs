@@ -0,0 +1,128 @@
+use std::{fmt::Display, path::PathBuf};
+
+use serde::Serialize;
+
+use crate::{
+    backend::Backend,
+    config::{self, Config},
+};
+
+/// How a preflight check went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Pass,
+    /// Works, but something deserves a look.
+    Warn,
+    Fail,
+    /// Not run because an earlier check failed.
+    Skip,
+}
+
+impl Verdict {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Verdict::Pass => "✓",
+            Verdict::Warn => "!",
+            Verdict::Fail => "✗",
+            Verdict::Skip => "-",
+        }
+    }
+}
+
+/// One line of the checklist shown by `nordvpn-tui doctor`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub verdict: Verdict,
+    pub detail: String,
+    pub remedy: Option<&'static str>,
+}
+
+impl Check {
+    pub fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            verdict: Verdict::Pass,
+            detail: detail.into(),
+            remedy: None,
+        }
+    }
+
+    pub fn warn(name: &'static str, detail: impl Into<String>, remedy: &'static str) -> Self {
+        Self {
+            name,
+            verdict: Verdict::Warn,
+            detail: detail.into(),
+            remedy: Some(remedy),
+        }
+    }
+
+    pub fn fail(name: &'static str, detail: impl Into<String>, remedy: &'static str) -> Self {
+        Self {
+            name,
+            verdict: Verdict::Fail,
+            detail: detail.into(),
+            remedy: Some(remedy),
+        }
+    }
+
+    pub fn skip(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            verdict: Verdict::Skip,
+            detail: detail.into(),
+            remedy: None,
+        }
+    }
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}: {}",
+            self.verdict.symbol(),
+            self.name,
+            self.detail
+        )
+    }
+}
+
+/// The backend's checks followed by the config file's.
+pub fn run(backend: &dyn Backend, config: Option<&str>) -> Vec<Check> {
+    let mut checks = backend.preflight();
+    checks.push(check_config(config));
+    checks
+}
+
+/// Whether nothing failed; warnings and skipped checks are fine.
+pub fn passed(checks: &[Check]) -> bool {
+    checks.iter().all(|check| check.verdict != Verdict::Fail)
+}
+
+fn check_config(config: Option<&str>) -> Check {
+    const NAME: &str = "Config";
+    let path = config
+        .map(PathBuf::from)
+        .unwrap_or_else(Config::default_path);
+    match Config::load(config) {
+        Ok(_) if config.is_none() && !path.exists() => Check::pass(
+            NAME,
+            format!("{} not found, using defaults", path.display()),
+        ),
+        Ok(config) if config.warnings.is_empty() => Check::pass(NAME, path.display().to_string()),
+        Ok(config) => Check::warn(
+            NAME,
+            config.warnings.join("; "),
+            "Fix or remove the entries mentioned; they are ignored for now.",
+        ),
+        // Like a failed reload, a broken file leaves the defaults in use
+        // rather than keeping the interface from starting.
+        Err(e) => Check::warn(
+            NAME,
+            format!("{}: {}", path.display(), config::summarize(&e.to_string())),
+            "Fix the file; the built-in defaults are used until then.",
+        ),
+    }
+}
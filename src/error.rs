@@ -1,12 +1,209 @@
 #![allow(dead_code)]
 
+use std::fmt::Display;
+
+use crate::{backend::CommandOutput, status::clean_line};
+
+/// Exit code of the subcommands for errors without a more specific one.
+pub const EXIT_FAILURE: i32 = 1;
+/// `nordvpn` rejected the request for a reason we do not recognise.
+pub const EXIT_REJECTED: i32 = 4;
+
+/// Failures of the NordVPN CLI recognised from what it prints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NordVpnError {
+    NotInstalled,
+    DaemonNotRunning,
+    PermissionDenied,
+    NotLoggedIn,
+    UnknownLocation,
+    Timeout,
+    NoInternet,
+}
+
+impl NordVpnError {
+    /// Looks for one of the CLI's known messages in its output.
+    pub fn detect(output: &str) -> Option<Self> {
+        let output = output.to_lowercase();
+        let found = |needles: &[&str]| needles.iter().any(|n| output.contains(n));
+
+        // The daemon messages mention the socket, so they go first.
+        if found(&["permission denied"]) {
+            Some(NordVpnError::PermissionDenied)
+        } else if found(&["nordvpnd.sock not found", "cannot reach system daemon"]) {
+            Some(NordVpnError::DaemonNotRunning)
+        } else if found(&["not logged in", "log in first"]) {
+            Some(NordVpnError::NotLoggedIn)
+        } else if found(&[
+            "does not exist",
+            "server is not available",
+            "not available at the moment",
+        ]) {
+            Some(NordVpnError::UnknownLocation)
+        } else if found(&["timed out", "timeout"]) {
+            Some(NordVpnError::Timeout)
+        } else if found(&[
+            "check your internet connection",
+            "no internet",
+            "network is unreachable",
+        ]) {
+            Some(NordVpnError::NoInternet)
+        } else {
+            None
+        }
+    }
+
+    /// What the user can do about it.
+    pub fn remedy(&self) -> &'static str {
+        match self {
+            NordVpnError::NotInstalled => {
+                "Install the NordVPN client and make sure `nordvpn` is on your PATH."
+            }
+            NordVpnError::DaemonNotRunning => {
+                "Start the daemon with `sudo systemctl enable --now nordvpnd`."
+            }
+            NordVpnError::PermissionDenied => {
+                "Add yourself to the nordvpn group with `sudo usermod -aG nordvpn $USER`, then log out and back in."
+            }
+            NordVpnError::NotLoggedIn => "Log in with `nordvpn login` first.",
+            NordVpnError::UnknownLocation => {
+                "Check the name with `nordvpn-tui countries` or `nordvpn-tui cities <country>`; some servers do not support the current technology."
+            }
+            NordVpnError::Timeout => "Try again, or pick another server or technology.",
+            NordVpnError::NoInternet => "Check your network connection and try again.",
+        }
+    }
+
+    /// Exit code of the subcommands, so scripts can tell failures apart.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            NordVpnError::UnknownLocation => 5,
+            NordVpnError::NotLoggedIn => 6,
+            NordVpnError::DaemonNotRunning => 7,
+            NordVpnError::PermissionDenied => 8,
+            NordVpnError::Timeout => 9,
+            NordVpnError::NoInternet => 10,
+            NordVpnError::NotInstalled => 11,
+        }
+    }
+
+    /// Name used in `--json` output.
+    pub fn name(&self) -> &'static str {
+        match self {
+            NordVpnError::NotInstalled => "not-installed",
+            NordVpnError::DaemonNotRunning => "daemon-not-running",
+            NordVpnError::PermissionDenied => "permission-denied",
+            NordVpnError::NotLoggedIn => "not-logged-in",
+            NordVpnError::UnknownLocation => "unknown-location",
+            NordVpnError::Timeout => "timeout",
+            NordVpnError::NoInternet => "no-internet",
+        }
+    }
+}
+
+impl Display for NordVpnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NordVpnError::NotInstalled => write!(f, "The NordVPN client is not installed"),
+            NordVpnError::DaemonNotRunning => write!(f, "The NordVPN daemon is not running"),
+            NordVpnError::PermissionDenied => {
+                write!(f, "Permission denied talking to the NordVPN daemon")
+            }
+            NordVpnError::NotLoggedIn => write!(f, "You are not logged in to NordVPN"),
+            NordVpnError::UnknownLocation => {
+                write!(f, "NordVPN does not know or cannot use that location")
+            }
+            NordVpnError::Timeout => write!(f, "The connection timed out"),
+            NordVpnError::NoInternet => write!(f, "There is no internet connection"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum AppError {
     Io(std::io::Error),
-    Command(std::process::ExitStatus),
+    /// `nordvpn` ran but rejected the request.
+    Command(CommandOutput),
+    /// `nordvpn` failed in a way we recognise.
+    NordVpn(NordVpnError, Option<CommandOutput>),
     Utf8(std::string::FromUtf8Error),
     Toml(toml::de::Error),
     ParseColor(ratatui::style::ParseColorError),
+    Json(serde_json::Error),
+    Cancelled,
+}
+
+impl AppError {
+    /// Error for a command that exited with a failure status.
+    pub fn from_output(output: CommandOutput) -> Self {
+        match NordVpnError::detect(&format!("{}\n{}", output.stdout, output.stderr)) {
+            Some(kind) => AppError::NordVpn(kind, Some(output)),
+            None => AppError::Command(output),
+        }
+    }
+
+    /// The failed command's output, for the errors that have one.
+    pub fn output(&self) -> Option<&CommandOutput> {
+        match self {
+            AppError::Command(output) | AppError::NordVpn(_, Some(output)) => Some(output),
+            _ => None,
+        }
+    }
+
+    pub fn remedy(&self) -> Option<&'static str> {
+        match self {
+            AppError::NordVpn(kind, _) => Some(kind.remedy()),
+            _ => None,
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::NordVpn(kind, _) => kind.exit_code(),
+            AppError::Command(_) => EXIT_REJECTED,
+            _ => EXIT_FAILURE,
+        }
+    }
+}
+
+impl Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::Io(e) => write!(f, "{}", e),
+            AppError::Command(output) => {
+                write!(f, "nordvpn failed ({})", output.status)?;
+                let first = output
+                    .stderr
+                    .lines()
+                    .chain(output.stdout.lines())
+                    .map(clean_line)
+                    .find(|line| !line.is_empty());
+                match first {
+                    Some(line) => write!(f, ": {}", line),
+                    None => Ok(()),
+                }
+            }
+            AppError::NordVpn(kind, _) => write!(f, "{}", kind),
+            AppError::Utf8(e) => write!(f, "nordvpn printed invalid UTF-8: {}", e),
+            AppError::Toml(e) => write!(f, "invalid config: {}", e),
+            AppError::ParseColor(e) => write!(f, "invalid color: {}", e),
+            AppError::Json(e) => write!(f, "invalid JSON: {}", e),
+            AppError::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::Io(e) => Some(e),
+            AppError::Utf8(e) => Some(e),
+            AppError::Toml(e) => Some(e),
+            AppError::ParseColor(e) => Some(e),
+            AppError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
 impl From<std::io::Error> for AppError {
@@ -32,3 +229,9 @@ impl From<ratatui::style::ParseColorError> for AppError {
         Self::ParseColor(e)
     }
 }
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
@@ -0,0 +1,125 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{data::Location, error::AppError, paths};
+
+/// A country, or a city within it, pinned by the user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Favorite {
+    pub country: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+}
+
+impl std::fmt::Display for Favorite {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.city {
+            Some(city) => write!(f, "{} ({})", city, self.country),
+            None => write!(f, "{}", self.country),
+        }
+    }
+}
+
+impl Favorite {
+    pub fn location(&self) -> Location {
+        match &self.city {
+            Some(city) => Location::City(city.clone()),
+            None => Location::Country(self.country.clone()),
+        }
+    }
+}
+
+/// Favorites list, saved as JSON after every change.
+///
+/// `Favorites::default()` has no backing file and never touches the disk.
+#[derive(Debug, Default)]
+pub struct Favorites {
+    path: Option<PathBuf>,
+    items: Vec<Favorite>,
+    /// Why the file could not be parsed; it is moved aside before the
+    /// first save.
+    broken: Option<String>,
+    backed_up: bool,
+}
+
+impl Favorites {
+    pub fn default_path() -> PathBuf {
+        paths::data_dir().join("favorites.json")
+    }
+
+    /// Where a file that could not be parsed is kept.
+    pub fn backup_path(path: &Path) -> PathBuf {
+        path.with_extension("json.bak")
+    }
+
+    /// Loads `path`, starting empty if it does not exist yet or cannot be
+    /// parsed. A broken file is renamed to `backup_path` before the first
+    /// save instead of being overwritten.
+    pub fn load(path: PathBuf) -> Result<Self, AppError> {
+        let mut broken = None;
+        let items = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                log::warn!("ignoring {}: {}", path.display(), e);
+                broken = Some(e.to_string());
+                Vec::new()
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            path: Some(path),
+            items,
+            broken,
+            backed_up: false,
+        })
+    }
+
+    /// What went wrong reading the file, for the status pane.
+    pub fn warning(&self) -> Option<String> {
+        let (path, error) = (self.path.as_ref()?, self.broken.as_ref()?);
+        Some(format!(
+            "Favorites could not be read ({}), starting empty; the file is kept as {}",
+            error,
+            Self::backup_path(path).display()
+        ))
+    }
+
+    pub fn items(&self) -> &[Favorite] {
+        &self.items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn contains(&self, favorite: &Favorite) -> bool {
+        self.items.contains(favorite)
+    }
+
+    /// Adds `favorite`, or removes it if it is already there.
+    pub fn toggle(&mut self, favorite: Favorite) -> Result<(), AppError> {
+        if let Some(i) = self.items.iter().position(|f| *f == favorite) {
+            self.items.remove(i);
+        } else {
+            self.items.push(favorite);
+        }
+        self.save()
+    }
+
+    fn save(&mut self) -> Result<(), AppError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        if self.broken.is_some() && !self.backed_up {
+            std::fs::rename(path, Self::backup_path(path))?;
+            self.backed_up = true;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(&self.items)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+const SCORE_MATCH: i64 = 16;
+const BONUS_FIRST_CHAR: i64 = 12;
+const BONUS_BOUNDARY: i64 = 8;
+const BONUS_CONSECUTIVE: i64 = 8;
+const PENALTY_GAP_START: i64 = 3;
+const PENALTY_GAP_EXTENSION: i64 = 1;
+
+/// How well a pattern matched a candidate, and where.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub score: i64,
+    /// Char indexes of the candidate that matched the pattern, in order.
+    pub positions: Vec<usize>,
+}
+
+/// fzf-style fuzzy match: every pattern char has to appear in `candidate`
+/// in order, case-insensitively, with spaces standing in for underscores.
+///
+/// The tightest window containing the match is scored, rewarding runs of
+/// consecutive chars and matches at the start of words.
+pub fn fuzzy_match(pattern: &str, candidate: &str) -> Option<Match> {
+    let pattern: Vec<char> = pattern.chars().map(fold).collect();
+    let chars: Vec<char> = candidate.chars().collect();
+    let folded: Vec<char> = chars.iter().copied().map(fold).collect();
+
+    if pattern.is_empty() {
+        return Some(Match {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+
+    // Forward pass: where the earliest complete match ends.
+    let mut p = 0;
+    let mut end = None;
+    for (i, c) in folded.iter().enumerate() {
+        if *c == pattern[p] {
+            p += 1;
+            if p == pattern.len() {
+                end = Some(i);
+                break;
+            }
+        }
+    }
+    let end = end?;
+
+    // Backward pass from there: the latest start, so the window is as tight as it gets.
+    let mut positions = Vec::with_capacity(pattern.len());
+    let mut p = pattern.len();
+    for i in (0..=end).rev() {
+        if folded[i] == pattern[p - 1] {
+            positions.push(i);
+            p -= 1;
+            if p == 0 {
+                break;
+            }
+        }
+    }
+    positions.reverse();
+
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+    for &i in &positions {
+        score += SCORE_MATCH;
+        if i == 0 {
+            score += BONUS_FIRST_CHAR;
+        } else if is_boundary(chars[i - 1], chars[i]) {
+            score += BONUS_BOUNDARY;
+        }
+        match previous {
+            Some(prev) if prev + 1 == i => score += BONUS_CONSECUTIVE,
+            Some(prev) => {
+                score -= PENALTY_GAP_START + PENALTY_GAP_EXTENSION * (i - prev - 2) as i64
+            }
+            None => {}
+        }
+        previous = Some(i);
+    }
+
+    Some(Match { score, positions })
+}
+
+/// Indexes of the items matching `pattern`, best first; ties keep their
+/// original order.
+pub fn filter(pattern: &str, items: &[String]) -> Vec<(usize, Match)> {
+    let mut matches: Vec<_> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| fuzzy_match(pattern, item).map(|m| (i, m)))
+        .collect();
+    matches.sort_by_key(|(_, m)| std::cmp::Reverse(m.score));
+    matches
+}
+
+fn fold(c: char) -> char {
+    match c {
+        '_' => ' ',
+        c => c.to_lowercase().next().unwrap_or(c),
+    }
+}
+
+fn is_boundary(previous: char, c: char) -> bool {
+    !previous.is_alphanumeric() || (previous.is_lowercase() && c.is_uppercase())
+}
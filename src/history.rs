@@ -0,0 +1,122 @@
+use std::{
+    io::Write,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{data::Location, error::AppError, paths};
+
+/// How many entries the history view shows.
+const RECENT: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Connected,
+    Failed,
+    Cancelled,
+}
+
+/// One connect attempt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub location: Location,
+    /// Seconds since the Unix epoch when the attempt finished.
+    pub timestamp: u64,
+    pub outcome: Outcome,
+    pub duration_ms: u64,
+}
+
+impl Entry {
+    pub fn new(location: Location, outcome: Outcome, duration: Duration) -> Self {
+        Self {
+            location,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            outcome,
+            duration_ms: duration.as_millis() as u64,
+        }
+    }
+
+    /// `just now`, `5m ago`, `3h ago`, `2d ago`.
+    pub fn age(&self) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let secs = now.saturating_sub(self.timestamp);
+
+        match secs {
+            0..=59 => "just now".to_string(),
+            60..=3_599 => format!("{}m ago", secs / 60),
+            3_600..=86_399 => format!("{}h ago", secs / 3_600),
+            _ => format!("{}d ago", secs / 86_400),
+        }
+    }
+}
+
+/// Connect attempts, appended to a JSON-lines file as they happen.
+///
+/// `History::default()` has no backing file and never touches the disk.
+#[derive(Debug, Default)]
+pub struct History {
+    path: Option<PathBuf>,
+    entries: Vec<Entry>,
+}
+
+impl History {
+    pub fn default_path() -> PathBuf {
+        paths::data_dir().join("history.jsonl")
+    }
+
+    /// Loads `path`, skipping lines it cannot parse and starting empty if
+    /// the file does not exist yet.
+    pub fn load(path: PathBuf) -> Result<Self, AppError> {
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(content) => content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            path: Some(path),
+            entries,
+        })
+    }
+
+    pub fn record(&mut self, entry: Entry) -> Result<(), AppError> {
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?;
+            writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        }
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Most recent entries first.
+    pub fn recent(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().rev().take(RECENT)
+    }
+
+    /// Where the last successful connection went.
+    pub fn last_location(&self) -> Option<&Location> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|e| e.outcome == Outcome::Connected)
+            .map(|e| &e.location)
+    }
+}
@@ -0,0 +1,384 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+/// Everything a key can be bound to in normal mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Select,
+    Down,
+    Up,
+    Top,
+    Bottom,
+    Back,
+    Search,
+    Quit,
+    /// Cancels the running command, or quits when there is none.
+    Cancel,
+    Disconnect,
+    QuickConnect,
+    ConnectServer,
+    ConnectCountry,
+    Reconnect,
+    History,
+    Favorite,
+    Favorites,
+    Settings,
+    Toggle,
+    Technology,
+    Groups,
+    Help,
+    Doctor,
+}
+
+impl Action {
+    pub const ALL: [Action; 23] = [
+        Action::Select,
+        Action::Down,
+        Action::Up,
+        Action::Top,
+        Action::Bottom,
+        Action::Back,
+        Action::Search,
+        Action::Quit,
+        Action::Cancel,
+        Action::Disconnect,
+        Action::QuickConnect,
+        Action::ConnectServer,
+        Action::ConnectCountry,
+        Action::Reconnect,
+        Action::History,
+        Action::Favorite,
+        Action::Favorites,
+        Action::Settings,
+        Action::Toggle,
+        Action::Technology,
+        Action::Groups,
+        Action::Help,
+        Action::Doctor,
+    ];
+
+    /// Name used in the `[keys]` table of `config.toml`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Select => "select",
+            Action::Down => "down",
+            Action::Up => "up",
+            Action::Top => "top",
+            Action::Bottom => "bottom",
+            Action::Back => "back",
+            Action::Search => "search",
+            Action::Quit => "quit",
+            Action::Cancel => "cancel",
+            Action::Disconnect => "disconnect",
+            Action::QuickConnect => "quick-connect",
+            Action::ConnectServer => "connect-server",
+            Action::ConnectCountry => "connect-country",
+            Action::Reconnect => "reconnect",
+            Action::History => "history",
+            Action::Favorite => "favorite",
+            Action::Favorites => "favorites",
+            Action::Settings => "settings",
+            Action::Toggle => "toggle",
+            Action::Technology => "technology",
+            Action::Groups => "groups",
+            Action::Help => "help",
+            Action::Doctor => "doctor",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    /// Short description shown in the instruction bar and the help view.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Select => "Select",
+            Action::Down => "Down",
+            Action::Up => "Up",
+            Action::Top => "Top",
+            Action::Bottom => "Bottom",
+            Action::Back => "Back",
+            Action::Search => "Search",
+            Action::Quit => "Quit",
+            Action::Cancel => "Cancel / Quit",
+            Action::Disconnect => "Disconnect",
+            Action::QuickConnect => "Quick connect",
+            Action::ConnectServer => "Server",
+            Action::ConnectCountry => "Connect country",
+            Action::Reconnect => "Reconnect",
+            Action::History => "History",
+            Action::Favorite => "Favorite",
+            Action::Favorites => "Favorites",
+            Action::Settings => "Settings",
+            Action::Toggle => "Toggle",
+            Action::Technology => "Technology",
+            Action::Groups => "Groups",
+            Action::Help => "Help",
+            Action::Doctor => "Diagnostics",
+        }
+    }
+}
+
+/// A single key press with its modifiers.
+///
+/// Shift is folded into the character for printable keys, so `H` and
+/// `shift-h` are the same chord.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        let code = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            code => code,
+        };
+        Self { code, modifiers }
+    }
+
+    /// Parses `q`, `H`, `shift-h`, `ctrl-c`, `esc`, `enter`, `space`, `f5`, ...
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = input;
+        loop {
+            let lower = rest.to_lowercase();
+            let (prefix, modifier) = if lower.starts_with("ctrl-") {
+                ("ctrl-", KeyModifiers::CONTROL)
+            } else if lower.starts_with("alt-") {
+                ("alt-", KeyModifiers::ALT)
+            } else if lower.starts_with("shift-") {
+                ("shift-", KeyModifiers::SHIFT)
+            } else {
+                break;
+            };
+            // `ctrl--` binds ctrl and the minus key.
+            if rest.len() == prefix.len() {
+                break;
+            }
+            modifiers |= modifier;
+            rest = &rest[prefix.len()..];
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "esc" | "escape" => KeyCode::Esc,
+                "enter" | "return" => KeyCode::Enter,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "tab" => KeyCode::Tab,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "delete" | "del" => KeyCode::Delete,
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key `{}`", input)),
+                },
+            },
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+impl Display for KeyChord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) if c.is_uppercase() => write!(f, "Shift-{}", c),
+            KeyCode::Char(c) => write!(f, "{}", c.to_uppercase()),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Backspace => write!(f, "Backspace"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::Up => write!(f, "Up"),
+            KeyCode::Down => write!(f, "Down"),
+            KeyCode::Left => write!(f, "Left"),
+            KeyCode::Right => write!(f, "Right"),
+            KeyCode::Home => write!(f, "Home"),
+            KeyCode::End => write!(f, "End"),
+            KeyCode::PageUp => write!(f, "PageUp"),
+            KeyCode::PageDown => write!(f, "PageDown"),
+            KeyCode::Delete => write!(f, "Delete"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// One or more chords pressed in a row, e.g. `g g`.
+type Sequence = Vec<KeyChord>;
+
+fn parse_sequence(input: &str) -> Result<Sequence, String> {
+    let sequence = input
+        .split_whitespace()
+        .map(KeyChord::parse)
+        .collect::<Result<Sequence, _>>()?;
+    if sequence.is_empty() {
+        return Err("empty key binding".to_string());
+    }
+    Ok(sequence)
+}
+
+const DEFAULT_BINDINGS: &[(&str, Action)] = &[
+    ("enter", Action::Select),
+    ("j", Action::Down),
+    ("down", Action::Down),
+    ("k", Action::Up),
+    ("up", Action::Up),
+    ("g g", Action::Top),
+    ("G", Action::Bottom),
+    ("h", Action::Back),
+    ("/", Action::Search),
+    ("i", Action::Search),
+    ("q", Action::Quit),
+    ("esc", Action::Cancel),
+    ("D", Action::Disconnect),
+    ("C", Action::QuickConnect),
+    ("n", Action::ConnectServer),
+    ("c", Action::ConnectCountry),
+    ("r", Action::Reconnect),
+    ("H", Action::History),
+    ("f", Action::Favorite),
+    ("F", Action::Favorites),
+    ("s", Action::Settings),
+    ("space", Action::Toggle),
+    ("t", Action::Technology),
+    ("p", Action::Groups),
+    ("?", Action::Help),
+    ("d", Action::Doctor),
+];
+
+/// Normal-mode key bindings: the defaults above, overridden by the `[keys]`
+/// table of the config, e.g.
+///
+/// ```toml
+/// [keys]
+/// "ctrl-c" = "quit"
+/// "q" = "none"
+/// ```
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Sequence, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            bindings: DEFAULT_BINDINGS
+                .iter()
+                .map(|(keys, action)| (parse_sequence(keys).unwrap(), *action))
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    /// Binds `keys` to `action`, or unbinds it when `action` is `none`.
+    fn bind(&mut self, keys: &str, action: &str) -> Result<(), String> {
+        let sequence = parse_sequence(keys)?;
+        let action = match action {
+            "none" => None,
+            name => {
+                Some(Action::from_name(name).ok_or_else(|| format!("unknown action `{}`", name))?)
+            }
+        };
+
+        self.bindings.retain(|(bound, _)| *bound != sequence);
+        if let Some(action) = action {
+            self.bindings.push((sequence, action));
+        }
+        Ok(())
+    }
+
+    /// Feeds a key press, returning the action once `pending` plus this key
+    /// completes a binding. Keys that start a longer binding stay in `pending`.
+    pub fn resolve(&self, pending: &mut Sequence, event: KeyEvent) -> Option<Action> {
+        pending.push(KeyChord::from(event));
+        loop {
+            if let Some((_, action)) = self.bindings.iter().find(|(keys, _)| keys == pending) {
+                pending.clear();
+                return Some(*action);
+            }
+            if self
+                .bindings
+                .iter()
+                .any(|(keys, _)| keys.starts_with(pending))
+            {
+                return None;
+            }
+            // Not going anywhere: start over from the key just pressed.
+            match pending.pop() {
+                Some(last) if !pending.is_empty() => {
+                    pending.clear();
+                    pending.push(last);
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// The keys bound to `action` as shown to the user, e.g. `J | Down`.
+    pub fn describe(&self, action: Action) -> Option<String> {
+        let keys: Vec<String> = self
+            .bindings
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(keys, _)| {
+                keys.iter()
+                    .map(|chord| chord.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        (!keys.is_empty()).then(|| keys.join(" | "))
+    }
+}
+
+impl<'de> Deserialize<'de> for Keymap {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let table = BTreeMap::<String, String>::deserialize(deserializer)?;
+        let mut keymap = Keymap::default();
+        for (keys, action) in &table {
+            keymap
+                .bind(keys, action)
+                .map_err(serde::de::Error::custom)?;
+        }
+        Ok(keymap)
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::{error::AppError, paths};
+
+/// The log is moved to `nordvpn-tui.log.old` once it grows past this.
+const MAX_SIZE: u64 = 1024 * 1024;
+
+/// `-v` raises the level one step at a time; warnings and errors are always
+/// written.
+pub fn level(verbose: u8) -> LevelFilter {
+    match verbose {
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// `$XDG_STATE_HOME/nordvpn-tui/nordvpn-tui.log`.
+pub fn default_path() -> PathBuf {
+    paths::state_dir().join("nordvpn-tui.log")
+}
+
+/// Sends the `log` macros of this crate to the file at `path`.
+pub fn init(path: PathBuf, verbose: u8) -> Result<(), AppError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    if fs::metadata(&path).is_ok_and(|m| m.len() > MAX_SIZE) {
+        fs::rename(&path, path.with_extension("log.old"))?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    writeln!(
+        file,
+        "--- nordvpn-tui {} started at {} (unix time), level {} ---",
+        env!("CARGO_PKG_VERSION"),
+        since_epoch.as_secs(),
+        level(verbose)
+    )?;
+
+    let logger = FileLogger {
+        file: Mutex::new(file),
+        level: level(verbose),
+        started: Instant::now(),
+    };
+    log::set_boxed_logger(Box::new(logger)).map_err(|e| AppError::Io(std::io::Error::other(e)))?;
+    log::set_max_level(level(verbose));
+    Ok(())
+}
+
+/// One line per record, stamped with the seconds since startup.
+struct FileLogger {
+    file: Mutex<File>,
+    level: LevelFilter,
+    started: Instant,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // Dependencies log too; only our own records are of interest.
+        metadata.level() <= self.level && metadata.target().starts_with("nordvpn_tui")
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let target = record
+            .target()
+            .strip_prefix("nordvpn_tui::")
+            .unwrap_or(record.target());
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(
+                file,
+                "{:>10.3} {:<5} {}: {}",
+                self.started.elapsed().as_secs_f64(),
+                record.level(),
+                target,
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
@@ -1,14 +1,35 @@
+use std::{sync::Arc, time::Duration};
+
 use app::App;
+use backend::{Backend, FakeBackend, NordVpnCli};
 use clap::Parser;
-use cli::Cli;
+use cli::{BackendKind, Cli, Command};
+use colors::ColorSupport;
+use commands::Runner;
 use dotenv::dotenv;
 use error::AppError;
+use favorites::Favorites;
+use history::History;
 
 mod app;
+mod backend;
 mod cli;
+mod colors;
+mod commands;
 mod config;
 mod data;
+mod doctor;
 mod error;
+mod favorites;
+mod fuzzy;
+mod history;
+mod keys;
+mod logging;
+mod paths;
+mod settings;
+mod shutdown;
+mod status;
+mod task;
 
 #[cfg(test)]
 mod tests;
@@ -17,13 +38,69 @@ fn main() -> Result<(), AppError> {
     dotenv().ok();
 
     let args = Cli::parse();
+    let log_path = logging::default_path();
+    if let Err(e) = logging::init(log_path.clone(), args.verbose) {
+        eprintln!(
+            "Warning: cannot write the log to {}: {:?}",
+            log_path.display(),
+            e
+        );
+    }
+    log::info!("{:?}", args);
+    shutdown::install_panic_hook(log_path);
+
+    let backend: Arc<dyn Backend> = match args.backend {
+        BackendKind::Nordvpn => Arc::new(NordVpnCli::default()),
+        BackendKind::Fake => Arc::new(FakeBackend::default().with_delay(Duration::from_secs(2))),
+    };
+
+    if let Some(command) = args.command.clone() {
+        let code = run_command(command, &args, backend).unwrap_or_else(|e| {
+            report(&e);
+            e.exit_code()
+        });
+        std::process::exit(code);
+    }
+
+    let colors = args.colors.unwrap_or_else(ColorSupport::detect);
+
     let mut terminal = ratatui::init();
-    let mut app = App::init(args.config).unwrap_or_else(|e| {
-        eprintln!("Error: {:?}", e);
-        std::process::exit(1);
+    if let Err(e) = shutdown::handle_signals() {
+        log::warn!("cannot handle signals: {}", e);
+    }
+    let mut app = App::init(args.config, backend, colors).unwrap_or_else(|e| {
+        ratatui::restore();
+        report(&e);
+        std::process::exit(e.exit_code());
     });
 
     let app_result = app.run(&mut terminal);
     ratatui::restore();
-    app_result
+    if let Some(signal) = shutdown::received() {
+        log::info!("quit: {}", shutdown::name(signal));
+        eprintln!("nordvpn-tui: {}", shutdown::name(signal));
+        std::process::exit(shutdown::exit_code(signal));
+    }
+    if let Err(e) = app_result {
+        report(&e);
+        std::process::exit(e.exit_code());
+    }
+    Ok(())
+}
+
+/// Prints a fatal error and what to do about it.
+fn report(error: &AppError) {
+    log::error!("{:?}", error);
+    eprintln!("Error: {}", error);
+    if let Some(remedy) = error.remedy() {
+        eprintln!("{}", remedy);
+    }
+}
+
+fn run_command(command: Command, args: &Cli, backend: Arc<dyn Backend>) -> Result<i32, AppError> {
+    let mut runner = Runner::new(backend, args.json)
+        .with_config(args.config.clone())
+        .with_favorites(Favorites::load(Favorites::default_path())?)
+        .with_history(History::load(History::default_path())?);
+    runner.run(command, &mut std::io::stdout().lock())
 }
@@ -0,0 +1,36 @@
+use std::path::PathBuf;
+
+const APP_DIR: &str = "nordvpn-tui";
+
+/// `$XDG_CONFIG_HOME/nordvpn-tui`, defaulting to `~/.config/nordvpn-tui`.
+pub fn config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+/// `$XDG_DATA_HOME/nordvpn-tui`, defaulting to `~/.local/share/nordvpn-tui`.
+pub fn data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// `$XDG_STATE_HOME/nordvpn-tui`, defaulting to `~/.local/state/nordvpn-tui`.
+pub fn state_dir() -> PathBuf {
+    xdg_dir("XDG_STATE_HOME", ".local/state")
+}
+
+fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    xdg_base(
+        std::env::var_os(var).map(PathBuf::from),
+        std::env::var_os("HOME").map(PathBuf::from),
+        fallback,
+    )
+    .join(APP_DIR)
+}
+
+/// The base directory an XDG variable set to `value` points at, falling
+/// back to `fallback` inside `home`.
+pub fn xdg_base(value: Option<PathBuf>, home: Option<PathBuf>, fallback: &str) -> PathBuf {
+    // The spec asks to ignore relative paths in these variables.
+    value
+        .filter(|p| p.is_absolute())
+        .unwrap_or_else(|| home.unwrap_or_else(|| PathBuf::from("./")).join(fallback))
+}
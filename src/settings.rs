@@ -0,0 +1,310 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::status::clean_line;
+
+/// Boolean settings that can be flipped with `nordvpn set <command> on|off`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Toggle {
+    KillSwitch,
+    AutoConnect,
+    ThreatProtection,
+    LanDiscovery,
+    Firewall,
+    Notify,
+    Ipv6,
+    Meshnet,
+    Obfuscate,
+    PostQuantum,
+}
+
+impl Toggle {
+    pub const ALL: [Toggle; 10] = [
+        Toggle::KillSwitch,
+        Toggle::AutoConnect,
+        Toggle::ThreatProtection,
+        Toggle::LanDiscovery,
+        Toggle::Firewall,
+        Toggle::Notify,
+        Toggle::Ipv6,
+        Toggle::Meshnet,
+        Toggle::Obfuscate,
+        Toggle::PostQuantum,
+    ];
+
+    /// Argument passed to `nordvpn set`.
+    fn command(&self) -> &'static str {
+        match self {
+            Toggle::KillSwitch => "killswitch",
+            Toggle::AutoConnect => "autoconnect",
+            Toggle::ThreatProtection => "threatprotectionlite",
+            Toggle::LanDiscovery => "lan-discovery",
+            Toggle::Firewall => "firewall",
+            Toggle::Notify => "notify",
+            Toggle::Ipv6 => "ipv6",
+            Toggle::Meshnet => "meshnet",
+            Toggle::Obfuscate => "obfuscate",
+            Toggle::PostQuantum => "post-quantum",
+        }
+    }
+
+    /// Maps a (lowercased) key of `nordvpn settings`, old names included.
+    fn from_key(key: &str) -> Option<Self> {
+        Some(match key {
+            "kill switch" => Toggle::KillSwitch,
+            "auto-connect" | "autoconnect" => Toggle::AutoConnect,
+            "threat protection lite" | "threat protection" | "cybersec" => Toggle::ThreatProtection,
+            "lan discovery" => Toggle::LanDiscovery,
+            "firewall" => Toggle::Firewall,
+            "notify" => Toggle::Notify,
+            "ipv6" => Toggle::Ipv6,
+            "meshnet" => Toggle::Meshnet,
+            "obfuscate" => Toggle::Obfuscate,
+            "post-quantum vpn" | "post-quantum" => Toggle::PostQuantum,
+            _ => return None,
+        })
+    }
+}
+
+impl Display for Toggle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Toggle::KillSwitch => "Kill Switch",
+            Toggle::AutoConnect => "Auto-connect",
+            Toggle::ThreatProtection => "Threat Protection",
+            Toggle::LanDiscovery => "LAN Discovery",
+            Toggle::Firewall => "Firewall",
+            Toggle::Notify => "Notify",
+            Toggle::Ipv6 => "IPv6",
+            Toggle::Meshnet => "Meshnet",
+            Toggle::Obfuscate => "Obfuscate",
+            Toggle::PostQuantum => "Post-quantum VPN",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Technology {
+    NordLynx,
+    OpenVpn,
+    NordWhisper,
+}
+
+impl Technology {
+    fn command(&self) -> &'static str {
+        match self {
+            Technology::NordLynx => "nordlynx",
+            Technology::OpenVpn => "openvpn",
+            Technology::NordWhisper => "nordwhisper",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "nordlynx" => Some(Technology::NordLynx),
+            "openvpn" => Some(Technology::OpenVpn),
+            "nordwhisper" => Some(Technology::NordWhisper),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Technology {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Technology::NordLynx => write!(f, "NordLynx"),
+            Technology::OpenVpn => write!(f, "OpenVPN"),
+            Technology::NordWhisper => write!(f, "NordWhisper"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Udp,
+    Tcp,
+}
+
+impl Protocol {
+    fn command(&self) -> &'static str {
+        match self {
+            Protocol::Udp => "udp",
+            Protocol::Tcp => "tcp",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "udp" => Some(Protocol::Udp),
+            "tcp" => Some(Protocol::Tcp),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Protocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Protocol::Udp => write!(f, "UDP"),
+            Protocol::Tcp => write!(f, "TCP"),
+        }
+    }
+}
+
+/// A single `nordvpn set ...` invocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Toggle(Toggle, bool),
+    Technology(Technology),
+    Protocol(Protocol),
+}
+
+impl Change {
+    /// Arguments passed to `nordvpn`.
+    pub fn args(&self) -> [&'static str; 3] {
+        match self {
+            Change::Toggle(toggle, enabled) => {
+                ["set", toggle.command(), if *enabled { "on" } else { "off" }]
+            }
+            Change::Technology(technology) => ["set", "technology", technology.command()],
+            Change::Protocol(protocol) => ["set", "protocol", protocol.command()],
+        }
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::Toggle(toggle, enabled) => write!(
+                f,
+                "Turning {} {}",
+                toggle,
+                if *enabled { "on" } else { "off" }
+            ),
+            Change::Technology(technology) => write!(f, "Switching to {}", technology),
+            Change::Protocol(protocol) => write!(f, "Switching to {}", protocol),
+        }
+    }
+}
+
+/// An entry of the technology/protocol picker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Choice {
+    Technology(Technology),
+    Protocol(Protocol),
+}
+
+impl Choice {
+    pub const ALL: [Choice; 5] = [
+        Choice::Technology(Technology::NordLynx),
+        Choice::Technology(Technology::OpenVpn),
+        Choice::Technology(Technology::NordWhisper),
+        Choice::Protocol(Protocol::Udp),
+        Choice::Protocol(Protocol::Tcp),
+    ];
+
+    pub fn change(&self) -> Change {
+        match self {
+            Choice::Technology(technology) => Change::Technology(*technology),
+            Choice::Protocol(protocol) => Change::Protocol(*protocol),
+        }
+    }
+
+    pub fn is_active(&self, settings: &Settings) -> bool {
+        match self {
+            Choice::Technology(technology) => settings.technology == Some(*technology),
+            Choice::Protocol(protocol) => settings.protocol == Some(*protocol),
+        }
+    }
+
+    /// Why this choice cannot be applied on top of `settings`, if it can't.
+    pub fn conflict(&self, settings: &Settings) -> Option<&'static str> {
+        let obfuscate = settings.get(Toggle::Obfuscate) == Some(true);
+        let post_quantum = settings.get(Toggle::PostQuantum) == Some(true);
+
+        match self {
+            Choice::Technology(technology) if obfuscate && *technology != Technology::OpenVpn => {
+                Some("obfuscation requires OpenVPN, turn it off first")
+            }
+            Choice::Technology(technology)
+                if post_quantum && *technology != Technology::NordLynx =>
+            {
+                Some("post-quantum VPN requires NordLynx, turn it off first")
+            }
+            Choice::Protocol(_) if settings.technology != Some(Technology::OpenVpn) => {
+                Some("protocol only applies to OpenVPN")
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Display for Choice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Choice::Technology(technology) => write!(f, "{}", technology),
+            Choice::Protocol(protocol) => write!(f, "{}", protocol),
+        }
+    }
+}
+
+/// Parsed output of `nordvpn settings`.
+///
+/// A toggle missing from `toggles` was not printed by the CLI, e.g.
+/// `Obfuscate` only shows up with OpenVPN.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Settings {
+    pub technology: Option<Technology>,
+    pub protocol: Option<Protocol>,
+    pub toggles: BTreeMap<Toggle, bool>,
+}
+
+impl Settings {
+    pub fn parse(output: &str) -> Self {
+        let mut settings = Settings::default();
+
+        for line in output.lines().map(clean_line) {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_lowercase();
+            let value = value.trim();
+
+            match key.as_str() {
+                "technology" => settings.technology = Technology::parse(value),
+                "protocol" => settings.protocol = Protocol::parse(value),
+                key => {
+                    if let (Some(toggle), Some(enabled)) =
+                        (Toggle::from_key(key), parse_bool(value))
+                    {
+                        settings.toggles.insert(toggle, enabled);
+                    }
+                }
+            }
+        }
+
+        settings
+    }
+
+    pub fn get(&self, toggle: Toggle) -> Option<bool> {
+        self.toggles.get(&toggle).copied()
+    }
+
+    /// `NordLynx` or `OpenVPN (TCP)`, as shown in the header.
+    pub fn connection_type(&self) -> Option<String> {
+        match (self.technology?, self.protocol) {
+            (Technology::OpenVpn, Some(protocol)) => {
+                Some(format!("{} ({})", Technology::OpenVpn, protocol))
+            }
+            (technology, _) => Some(technology.to_string()),
+        }
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.split_whitespace().next()?.to_lowercase().as_str() {
+        "enabled" | "on" | "true" => Some(true),
+        "disabled" | "off" | "false" => Some(false),
+        _ => None,
+    }
+}
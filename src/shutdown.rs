@@ -0,0 +1,83 @@
+use std::{
+    backtrace::Backtrace,
+    panic::PanicHookInfo,
+    path::PathBuf,
+    sync::atomic::{AtomicI32, Ordering},
+    thread,
+};
+
+use signal_hook::{
+    consts::{SIGHUP, SIGINT, SIGTERM},
+    iterator::Signals,
+};
+
+/// The last signal asking us to stop, 0 before any.
+static RECEIVED: AtomicI32 = AtomicI32::new(0);
+
+/// Logs panics and prints a short report instead of the default message.
+///
+/// Install it before `ratatui::init`, whose own hook restores the terminal
+/// and then calls this one.
+pub fn install_panic_hook(log_path: PathBuf) {
+    std::panic::set_hook(Box::new(move |info| {
+        log::error!("panic: {}\n{}", describe(info), Backtrace::force_capture());
+        eprintln!("nordvpn-tui crashed: {}", describe(info));
+        eprintln!(
+            "The details were written to {}; please attach it when reporting the bug.",
+            log_path.display()
+        );
+    }));
+}
+
+fn describe(info: &PanicHookInfo) -> String {
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    match info.location() {
+        Some(location) => format!("{} at {}", message, location),
+        None => message,
+    }
+}
+
+/// Turns SIGINT, SIGTERM and SIGHUP into a request to quit, picked up by
+/// the main loop through `received`. A second signal means the loop is
+/// stuck: the terminal is restored and the process ends right away.
+pub fn handle_signals() -> std::io::Result<()> {
+    let mut signals = Signals::new([SIGINT, SIGTERM, SIGHUP])?;
+    thread::spawn(move || {
+        for signal in signals.forever() {
+            log::info!("received signal {}", signal);
+            if RECEIVED.swap(signal, Ordering::SeqCst) != 0 {
+                ratatui::restore();
+                eprintln!("nordvpn-tui: {}", name(signal));
+                std::process::exit(exit_code(signal));
+            }
+        }
+    });
+    Ok(())
+}
+
+/// The signal received, if one asked us to quit.
+pub fn received() -> Option<i32> {
+    match RECEIVED.load(Ordering::SeqCst) {
+        0 => None,
+        signal => Some(signal),
+    }
+}
+
+pub fn name(signal: i32) -> &'static str {
+    match signal {
+        SIGINT => "interrupted",
+        SIGTERM => "terminated",
+        SIGHUP => "hung up",
+        _ => "stopped by a signal",
+    }
+}
+
+/// The shell convention for processes ended by a signal.
+pub fn exit_code(signal: i32) -> i32 {
+    128 + signal
+}
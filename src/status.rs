@@ -0,0 +1,332 @@
+use std::{fmt::Display, time::Duration};
+
+/// Connection state reported on the `Status:` line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum State {
+    Connected,
+    Connecting,
+    #[default]
+    Disconnected,
+    Other(String),
+}
+
+impl Display for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            State::Connected => write!(f, "Connected"),
+            State::Connecting => write!(f, "Connecting"),
+            State::Disconnected => write!(f, "Disconnected"),
+            State::Other(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Bytes moved through the tunnel, from the `Transfer:` line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Transfer {
+    pub received: u64,
+    pub sent: u64,
+}
+
+impl Display for Transfer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "↓ {} ↑ {}",
+            format_bytes(self.received),
+            format_bytes(self.sent)
+        )
+    }
+}
+
+/// Parsed output of `nordvpn status`.
+///
+/// Every field except `state` is optional: the CLI only prints them while
+/// connected, and older versions use different keys for some of them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Status {
+    pub state: State,
+    pub server: Option<String>,
+    pub hostname: Option<String>,
+    pub ip: Option<String>,
+    pub country: Option<String>,
+    pub city: Option<String>,
+    pub technology: Option<String>,
+    pub protocol: Option<String>,
+    pub transfer: Option<Transfer>,
+    pub uptime: Option<Duration>,
+}
+
+impl Status {
+    pub fn parse(output: &str) -> Self {
+        let mut status = Status::default();
+
+        for line in output.lines().map(clean_line) {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            if value.is_empty() {
+                continue;
+            }
+
+            match key.trim().to_lowercase().as_str() {
+                "status" => status.state = parse_state(value),
+                "server" => status.server = Some(value.to_string()),
+                "hostname" | "current server" => status.hostname = Some(value.to_string()),
+                "ip" | "your new ip" | "server ip" => status.ip = Some(value.to_string()),
+                "country" => status.country = Some(value.to_string()),
+                "city" => status.city = Some(value.to_string()),
+                "current technology" | "technology" => status.technology = Some(value.to_string()),
+                "current protocol" | "protocol" => status.protocol = Some(value.to_string()),
+                "transfer" => status.transfer = parse_transfer(value),
+                "uptime" => status.uptime = parse_uptime(value),
+                _ => {}
+            }
+        }
+
+        status
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.state == State::Connected
+    }
+
+    /// `City, Country`, or whichever of the two is known.
+    pub fn location(&self) -> Option<String> {
+        match (&self.city, &self.country) {
+            (Some(city), Some(country)) => Some(format!("{}, {}", city, country)),
+            (Some(place), None) | (None, Some(place)) => Some(place.clone()),
+            (None, None) => None,
+        }
+    }
+
+    /// One-line description used as the header title.
+    pub fn summary(&self) -> String {
+        let mut summary = self.state.to_string();
+
+        if let Some(place) = self.location() {
+            summary.push_str(&format!(" | {}", place));
+        }
+        if let Some(server) = self.hostname.as_ref().or(self.server.as_ref()) {
+            summary.push_str(&format!(" | {}", server));
+        }
+        if let Some(ip) = &self.ip {
+            summary.push_str(&format!(" | {}", ip));
+        }
+
+        summary
+    }
+
+    /// Labelled rows for the status pane, `status` and the waybar tooltip.
+    /// `since` is how long ago the status was fetched, added to the uptime.
+    pub fn rows(&self, since: Duration) -> Vec<(&'static str, String)> {
+        let mut rows = vec![("State", self.state.to_string())];
+        if let Some(server) = self.hostname.as_ref().or(self.server.as_ref()) {
+            rows.push(("Server", server.clone()));
+        }
+        if let Some(location) = self.location() {
+            rows.push(("Location", location));
+        }
+        if let Some(ip) = &self.ip {
+            rows.push(("IP", ip.clone()));
+        }
+        if let Some(technology) = &self.technology {
+            let protocol = self.protocol.as_deref().unwrap_or("");
+            rows.push((
+                "Technology",
+                format!("{} {}", technology, protocol)
+                    .trim_end()
+                    .to_string(),
+            ));
+        }
+        if let Some(uptime) = self.uptime {
+            rows.push(("Uptime", format_duration(uptime + since)));
+        }
+        if let Some(transfer) = self.transfer {
+            rows.push(("Transfer", transfer.to_string()));
+        }
+        rows
+    }
+
+    /// Fills a template such as `{country} {city} {ip}`. Fields that are not
+    /// known become empty, unknown placeholders are kept as they are and
+    /// `{{`/`}}` stand for literal braces.
+    pub fn render(&self, template: &str) -> String {
+        let mut rendered = String::new();
+        let mut rest = template;
+        while let Some(i) = rest.find(['{', '}']) {
+            rendered.push_str(&rest[..i]);
+            rest = &rest[i..];
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                rendered.push_str(&rest[..1]);
+                rest = &rest[2..];
+                continue;
+            }
+            let field = rest
+                .strip_prefix('{')
+                .and_then(|r| r.find('}').map(|end| &r[..end]));
+            match field.and_then(|name| self.field(name).map(|value| (name, value))) {
+                Some((name, value)) => {
+                    rendered.push_str(&value.unwrap_or_default());
+                    rest = &rest[name.len() + 2..];
+                }
+                None => {
+                    rendered.push_str(&rest[..1]);
+                    rest = &rest[1..];
+                }
+            }
+        }
+        rendered.push_str(rest);
+        rendered.trim().to_string()
+    }
+
+    /// Value of a template field, `None` when there is no such field.
+    fn field(&self, name: &str) -> Option<Option<String>> {
+        let value = match name {
+            "state" => Some(self.state.to_string()),
+            "server" => self.hostname.clone().or_else(|| self.server.clone()),
+            "hostname" => self.hostname.clone(),
+            "ip" => self.ip.clone(),
+            "country" => self.country.clone(),
+            "city" => self.city.clone(),
+            "location" => self.location(),
+            "technology" => self.technology.clone(),
+            "protocol" => self.protocol.clone(),
+            "uptime" => self.uptime.map(format_duration),
+            "received" => self.transfer.map(|t| format_bytes(t.received)),
+            "sent" => self.transfer.map(|t| format_bytes(t.sent)),
+            "transfer" => self.transfer.map(|t| t.to_string()),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// Strips ANSI escapes and the `\r`-driven spinner the CLI draws while it
+/// talks to the daemon, keeping only what would be visible on the line.
+pub fn clean_line(line: &str) -> String {
+    let visible = line
+        .split('\r')
+        .rev()
+        .find(|s| !s.trim().is_empty())
+        .unwrap_or("");
+
+    let mut out = String::with_capacity(visible.len());
+    let mut chars = visible.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            if chars.peek() == Some(&'[') {
+                chars.next();
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        out.push(c);
+    }
+
+    // The spinner frames can be left glued to the start of the text.
+    out.trim()
+        .trim_start_matches(['-', '\\', '|', '/'])
+        .trim()
+        .to_string()
+}
+
+fn parse_state(value: &str) -> State {
+    match value.to_lowercase().as_str() {
+        "connected" => State::Connected,
+        "connecting" => State::Connecting,
+        "disconnected" => State::Disconnected,
+        _ => State::Other(value.to_string()),
+    }
+}
+
+/// Parses `1.23 MiB received, 456.78 KiB sent`.
+fn parse_transfer(value: &str) -> Option<Transfer> {
+    let mut transfer = Transfer::default();
+
+    for part in value.split(',') {
+        let mut words = part.split_whitespace();
+        let amount: f64 = words.next()?.parse().ok()?;
+        let unit = words.next()?;
+        let direction = words.next()?;
+
+        let multiplier = match unit {
+            "B" => 1.0,
+            "KiB" => 1024.0,
+            "MiB" => 1024.0 * 1024.0,
+            "GiB" => 1024.0 * 1024.0 * 1024.0,
+            "TiB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+            _ => return None,
+        };
+        let bytes = (amount * multiplier).round() as u64;
+
+        match direction {
+            "received" => transfer.received = bytes,
+            "sent" => transfer.sent = bytes,
+            _ => return None,
+        }
+    }
+
+    Some(transfer)
+}
+
+/// Parses `1 day 2 hours 3 minutes 4 seconds`, any subset of the units.
+fn parse_uptime(value: &str) -> Option<Duration> {
+    let words: Vec<&str> = value.split_whitespace().collect();
+    if words.is_empty() || !words.len().is_multiple_of(2) {
+        return None;
+    }
+
+    let mut secs = 0;
+    for pair in words.chunks(2) {
+        let amount: u64 = pair[0].parse().ok()?;
+        let unit = match pair[1].trim_end_matches('s') {
+            "second" => 1,
+            "minute" => 60,
+            "hour" => 60 * 60,
+            "day" => 24 * 60 * 60,
+            _ => return None,
+        };
+        secs += amount * unit;
+    }
+
+    Some(Duration::from_secs(secs))
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.2} {}", value, UNITS[unit])
+    }
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (days, hours, minutes, seconds) = (
+        secs / 86_400,
+        secs % 86_400 / 3_600,
+        secs % 3_600 / 60,
+        secs % 60,
+    );
+
+    if days > 0 {
+        format!("{}d {:02}:{:02}:{:02}", days, hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    fmt::Display,
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    backend::{Backend, CancelToken, CommandOutput},
+    data::Location,
+    error::AppError,
+    settings::Change,
+    status::Status,
+};
+
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+#[derive(Debug, Clone)]
+pub enum Operation {
+    Connect(Location),
+    Disconnect,
+    Set(Change),
+}
+
+impl Operation {
+    /// Whether the backend stops early when the task is cancelled;
+    /// `nordvpn set` takes no cancel token.
+    pub fn is_cancellable(&self) -> bool {
+        !matches!(self, Operation::Set(_))
+    }
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operation::Connect(location) => write!(f, "Connecting to {}", location),
+            Operation::Disconnect => write!(f, "Disconnecting"),
+            Operation::Set(change) => write!(f, "{}", change),
+        }
+    }
+}
+
+/// A backend command that changes state, running on a worker thread.
+///
+/// The result is delivered over a channel and picked up by the event loop
+/// through `poll`.
+#[derive(Debug)]
+pub struct Task {
+    pub operation: Operation,
+    started: Instant,
+    cancel: CancelToken,
+    rx: Receiver<Result<CommandOutput, AppError>>,
+}
+
+impl Task {
+    pub fn spawn(backend: Arc<dyn Backend>, operation: Operation) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let cancel = CancelToken::default();
+
+        let op = operation.clone();
+        let token = cancel.clone();
+        thread::spawn(move || {
+            let result = match &op {
+                Operation::Connect(location) => backend.connect(location, &token),
+                Operation::Disconnect => backend.disconnect(&token),
+                Operation::Set(change) => backend.set(*change),
+            };
+            let _ = tx.send(result);
+        });
+
+        Self {
+            operation,
+            started: Instant::now(),
+            cancel,
+            rx,
+        }
+    }
+
+    /// Returns the result once the worker is done, `None` while it is still running.
+    pub fn poll(&self) -> Option<Result<CommandOutput, AppError>> {
+        match self.rx.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(AppError::Cancelled)),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn spinner(&self) -> &'static str {
+        SPINNER[(self.elapsed().as_millis() / 100) as usize % SPINNER.len()]
+    }
+}
+
+/// Re-reads `nordvpn status` in the background every `interval`, so the
+/// UI notices drops and connections made from another terminal.
+#[derive(Debug)]
+pub struct StatusPoller {
+    interval: Duration,
+    next: Instant,
+    rx: Option<Receiver<Result<Status, AppError>>>,
+}
+
+impl StatusPoller {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next: Instant::now() + interval,
+            rx: None,
+        }
+    }
+
+    /// Makes the next `tick` start a fresh refresh regardless of the interval,
+    /// dropping one that may have started before the state changed.
+    pub fn refresh_now(&mut self) {
+        self.next = Instant::now();
+        self.rx = None;
+    }
+
+    /// Starts a refresh when one is due and returns the result of a finished one.
+    pub fn tick(&mut self, backend: &Arc<dyn Backend>) -> Option<Result<Status, AppError>> {
+        if let Some(rx) = &self.rx {
+            return match rx.try_recv() {
+                Ok(result) => {
+                    self.rx = None;
+                    Some(result)
+                }
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => {
+                    self.rx = None;
+                    None
+                }
+            };
+        }
+
+        if Instant::now() >= self.next {
+            self.next = Instant::now() + self.interval;
+            let (tx, rx) = mpsc::channel();
+            let backend = backend.clone();
+            thread::spawn(move || {
+                let _ = tx.send(backend.status());
+            });
+            self.rx = Some(rx);
+        }
+        None
+    }
+}
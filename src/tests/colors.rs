@@ -0,0 +1,61 @@
+use ratatui::style::{Color, Modifier};
+
+use crate::{backend::FakeBackend, colors::ColorSupport};
+
+#[test]
+fn detects_color_support() {
+    use ColorSupport::*;
+
+    let detect = ColorSupport::from_env;
+    assert_eq!(detect(None, Some("truecolor"), Some("xterm")), TrueColor);
+    assert_eq!(detect(None, Some("24bit"), None), TrueColor);
+    assert_eq!(detect(None, None, Some("xterm-256color")), Ansi256);
+    assert_eq!(detect(None, None, Some("xterm")), Ansi16);
+    assert_eq!(detect(None, None, None), Ansi16);
+    assert_eq!(detect(None, None, Some("dumb")), Monochrome);
+    assert_eq!(detect(Some("1"), Some("truecolor"), None), Monochrome);
+    // An empty NO_COLOR does not count.
+    assert_eq!(detect(Some(""), Some("truecolor"), None), TrueColor);
+}
+
+#[test]
+fn downgrades_to_nearest_palette_entry() {
+    let rgb = Color::Rgb(0xff, 0x55, 0x55);
+    assert_eq!(ColorSupport::TrueColor.downgrade(rgb), rgb);
+    assert_eq!(ColorSupport::Ansi256.downgrade(rgb), Color::Indexed(203));
+    assert_eq!(ColorSupport::Ansi16.downgrade(rgb), Color::LightRed);
+    assert_eq!(ColorSupport::Monochrome.downgrade(rgb), Color::Reset);
+
+    // Grays land on the grayscale ramp rather than the cube.
+    assert_eq!(
+        ColorSupport::Ansi256.downgrade(Color::Rgb(0x1e, 0x1e, 0x1e)),
+        Color::Indexed(234)
+    );
+    assert_eq!(
+        ColorSupport::Ansi256.downgrade(Color::Rgb(0, 0, 0)),
+        Color::Indexed(16)
+    );
+    assert_eq!(
+        ColorSupport::Ansi16.downgrade(Color::Indexed(196)),
+        Color::LightRed
+    );
+    assert_eq!(ColorSupport::Ansi16.downgrade(Color::Blue), Color::Blue);
+}
+
+#[test]
+fn monochrome_marks_selection_with_modifiers() {
+    let mut app = super::app(&FakeBackend::default()).with_color_support(ColorSupport::Monochrome);
+    let buffer = super::draw(&mut app);
+
+    assert!(buffer
+        .content()
+        .iter()
+        .all(|cell| !matches!(cell.fg, Color::Rgb(..) | Color::Indexed(_))));
+    // The first country is selected.
+    let selected = buffer
+        .content()
+        .iter()
+        .find(|cell| cell.symbol() == "G")
+        .unwrap();
+    assert!(selected.modifier.contains(Modifier::REVERSED));
+}
@@ -0,0 +1,269 @@
+use std::{io::Write, sync::Arc};
+
+use crate::{
+    backend::FakeBackend,
+    cli::Command,
+    commands::{waybar_json, Runner, EXIT_DISCONNECTED, EXIT_OK},
+    data::Location,
+    error::EXIT_FAILURE,
+    error::{AppError, NordVpnError, EXIT_REJECTED},
+};
+
+fn status(format: Option<&str>) -> Command {
+    Command::Status {
+        format: format.map(str::to_string),
+        watch: false,
+        interval: 0,
+    }
+}
+
+fn run(runner: &mut Runner, command: Command) -> (i32, String) {
+    let mut out = Vec::new();
+    let code = runner.run(command, &mut out).unwrap();
+    (code, String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn status_exit_code_follows_the_connection() {
+    let backend = Arc::new(FakeBackend::default());
+    let mut runner = Runner::new(backend.clone(), false);
+
+    let (code, output) = run(&mut runner, status(None));
+    assert_eq!(code, EXIT_DISCONNECTED);
+    assert!(output.starts_with("State: Disconnected"));
+
+    let connect = Command::Connect {
+        country: "Italy".to_string(),
+        city: Some("Rome".to_string()),
+    };
+    assert_eq!(run(&mut runner, connect).0, EXIT_OK);
+    assert_eq!(
+        backend.connected_to(),
+        Some(Location::City("Rome".to_string()))
+    );
+
+    let (code, output) = run(&mut runner, status(None));
+    assert_eq!(code, EXIT_OK);
+    assert!(output.contains("Rome"));
+}
+
+#[test]
+fn rejected_commands_exit_with_their_own_code() {
+    let mut runner = Runner::new(Arc::new(FakeBackend::default()), false);
+
+    let connect = Command::Connect {
+        country: "Atlantis".to_string(),
+        city: None,
+    };
+    let error = runner.run(connect, &mut Vec::new()).unwrap_err();
+    assert!(matches!(
+        error,
+        AppError::NordVpn(NordVpnError::UnknownLocation, _)
+    ));
+    assert_eq!(error.exit_code(), 5);
+
+    let error = runner
+        .run(Command::Disconnect, &mut Vec::new())
+        .unwrap_err();
+    assert_eq!(error.exit_code(), EXIT_REJECTED);
+    assert_eq!(
+        error.to_string(),
+        "nordvpn failed (exit status: 1): You are not connected to NordVPN."
+    );
+
+    // Scripts asking for JSON get the error as JSON.
+    let mut runner = Runner::new(Arc::new(FakeBackend::default()), true);
+    let (code, output) = run(&mut runner, Command::Disconnect);
+    assert_eq!(code, EXIT_REJECTED);
+    let error: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(error["error"], "rejected");
+    assert_eq!(error["output"][0], "You are not connected to NordVPN.");
+}
+
+#[test]
+fn lists_print_one_name_per_line_or_json() {
+    let backend = Arc::new(FakeBackend::default());
+
+    let mut runner = Runner::new(backend.clone(), false);
+    let (code, output) = run(
+        &mut runner,
+        Command::Cities {
+            country: "Italy".to_string(),
+        },
+    );
+    assert_eq!(code, EXIT_OK);
+    assert_eq!(output, "Milan\nRome\n");
+
+    let mut runner = Runner::new(backend, true);
+    let (_, output) = run(
+        &mut runner,
+        Command::Cities {
+            country: "Italy".to_string(),
+        },
+    );
+    let cities: Vec<String> = serde_json::from_str(&output).unwrap();
+    assert_eq!(cities, vec!["Milan", "Rome"]);
+
+    let (code, output) = run(&mut runner, status(None));
+    assert_eq!(code, EXIT_DISCONNECTED);
+    let status: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(status["connected"], false);
+}
+
+/// Takes three lines, then fails like a closed pipe. Each line changes the
+/// connection: connect, disconnect a little later, connect again.
+struct Closing {
+    backend: Arc<FakeBackend>,
+    written: String,
+    lines: usize,
+}
+
+impl Write for Closing {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        use crate::backend::{Backend, CancelToken};
+
+        if self.lines == 3 {
+            return Err(std::io::ErrorKind::BrokenPipe.into());
+        }
+        self.written.push_str(&String::from_utf8_lossy(buf));
+        if buf.contains(&b'\n') {
+            self.lines += 1;
+            let rome = Location::City("Rome".to_string());
+            match self.lines {
+                2 => {
+                    // Long enough for the uptime in the tooltip to tick.
+                    let backend = self.backend.clone();
+                    std::thread::spawn(move || {
+                        std::thread::sleep(std::time::Duration::from_millis(1100));
+                        backend.disconnect(&CancelToken::default()).unwrap();
+                    });
+                }
+                _ => {
+                    self.backend
+                        .connect(&rome, &CancelToken::default())
+                        .unwrap();
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn status_templates_and_waybar_lines() {
+    let backend = Arc::new(FakeBackend::default());
+    let mut runner = Runner::new(backend.clone(), false);
+    let connect = Command::Connect {
+        country: "Italy".to_string(),
+        city: Some("Rome".to_string()),
+    };
+    run(&mut runner, connect);
+
+    let (code, output) = run(&mut runner, status(Some("{country} {city} [{nope}]")));
+    assert_eq!(code, EXIT_OK);
+    assert_eq!(output, "Italy Rome [{nope}]\n");
+
+    use crate::backend::Backend;
+    let line = waybar_json(&backend.status(), None);
+    assert_eq!(line["class"], "connected");
+    assert_eq!(line["text"], "Rome, Italy");
+    assert!(line["tooltip"]
+        .as_str()
+        .unwrap()
+        .contains("State: Connected"));
+
+    let line = waybar_json(&Ok(Default::default()), Some("{city}"));
+    assert_eq!(line["class"], "disconnected");
+    assert_eq!(line["text"], "");
+}
+
+#[test]
+fn watch_prints_changes_until_the_output_closes() {
+    let backend = Arc::new(FakeBackend::default());
+    let mut runner = Runner::new(backend.clone(), false);
+    let mut out = Closing {
+        backend,
+        written: String::new(),
+        lines: 0,
+    };
+    let watch = Command::Status {
+        format: None,
+        watch: true,
+        interval: 0,
+    };
+    assert!(runner.run(watch, &mut out).is_err());
+
+    let classes: Vec<String> = out
+        .written
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["class"].to_string())
+        .collect();
+    assert_eq!(
+        classes,
+        vec!["\"disconnected\"", "\"connected\"", "\"disconnected\""]
+    );
+}
+
+#[test]
+fn doctor_fails_with_a_remedy_until_logged_in() {
+    let dir = super::temp_dir("doctor-command");
+    let config = dir.join("config.toml").to_str().map(str::to_string);
+    let backend = Arc::new(FakeBackend::default());
+    let mut runner = Runner::new(backend.clone(), false).with_config(config.clone());
+
+    let (code, output) = run(&mut runner, Command::Doctor);
+    assert_eq!(code, EXIT_OK);
+    assert!(output.contains("✓ Login"));
+
+    backend.set_logged_in(false);
+    let (code, output) = run(&mut runner, Command::Doctor);
+    assert_eq!(code, EXIT_FAILURE);
+    let lines: Vec<&str> = output.lines().collect();
+    let login = lines.iter().position(|l| l.starts_with("✗ Login")).unwrap();
+    assert!(lines[login + 1].starts_with("    Log in with `nordvpn login`"));
+
+    let mut runner = Runner::new(backend, true).with_config(config);
+    let (_, output) = run(&mut runner, Command::Doctor);
+    let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(json["passed"], false);
+    assert!(json["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|c| c["name"] == "Login" && c["verdict"] == "fail"));
+}
+
+#[test]
+fn connect_keeps_the_city_inside_the_country() {
+    let backend = Arc::new(FakeBackend::default());
+    let mut runner = Runner::new(backend.clone(), false);
+
+    for (country, city) in [("Germany", "Rome"), ("Typo", "Berlin")] {
+        let connect = Command::Connect {
+            country: country.to_string(),
+            city: Some(city.to_string()),
+        };
+        let error = runner.run(connect, &mut Vec::new()).unwrap_err();
+        assert!(matches!(
+            error,
+            AppError::NordVpn(NordVpnError::UnknownLocation, None)
+        ));
+        assert_eq!(error.exit_code(), 5);
+    }
+    assert_eq!(backend.connected_to(), None);
+
+    // Spelled the way people type it.
+    let connect = Command::Connect {
+        country: "United_States".to_string(),
+        city: Some("new york".to_string()),
+    };
+    assert_eq!(run(&mut runner, connect).0, EXIT_OK);
+    assert_eq!(
+        backend.connected_to(),
+        Some(Location::City("New_York".to_string()))
+    );
+}
@@ -0,0 +1,159 @@
+use ratatui::style::Color;
+
+use crate::config::{ColorsConfig, Config};
+
+#[test]
+fn partial_config_keeps_defaults() {
+    let config = Config::parse(
+        r##"
+        [colors]
+        connected = "#00ff00"
+        "##,
+    )
+    .unwrap();
+
+    assert_eq!(config.colors.connected, Color::Rgb(0, 255, 0));
+    assert_eq!(
+        config.colors.disconnected,
+        ColorsConfig::default().disconnected
+    );
+    assert!(config.warnings.is_empty());
+    assert!(Config::parse("").unwrap().warnings.is_empty());
+}
+
+#[test]
+fn unknown_keys_are_warnings() {
+    let config = Config::parse(
+        r##"
+        font = "mono"
+
+        [colors]
+        conected = "#00ff00"
+        items = "#112233"
+        "##,
+    )
+    .unwrap();
+
+    assert_eq!(config.colors.items, Color::Rgb(0x11, 0x22, 0x33));
+    assert_eq!(
+        config.warnings,
+        vec![
+            "unknown key `font` ignored",
+            "unknown key `colors.conected` ignored",
+        ]
+    );
+
+    // Values that are there but wrong still fail loudly.
+    assert!(Config::parse("[colors]\nitems = \"#12\"").is_err());
+}
+
+#[test]
+fn config_dir_follows_xdg() {
+    use std::path::PathBuf;
+
+    use crate::paths::xdg_base;
+
+    let home = Some(PathBuf::from("/home/me"));
+    assert_eq!(
+        xdg_base(Some(PathBuf::from("/xdg")), home.clone(), ".config"),
+        PathBuf::from("/xdg")
+    );
+    // Relative values are ignored, as the spec asks.
+    assert_eq!(
+        xdg_base(Some(PathBuf::from("xdg")), home.clone(), ".config"),
+        PathBuf::from("/home/me/.config")
+    );
+    assert_eq!(
+        xdg_base(None, home, ".config"),
+        PathBuf::from("/home/me/.config")
+    );
+}
+
+#[test]
+fn missing_files_fall_back_to_defaults() {
+    let dir = super::temp_dir("config");
+    let default = dir.join("config.toml");
+
+    let config = Config::load_or(None, default.clone()).unwrap();
+    assert!(config.warnings.is_empty());
+
+    std::fs::write(&default, "[colors]\nbackground = \"#000000\"\n").unwrap();
+    let config = Config::load_or(None, default.clone()).unwrap();
+    assert_eq!(config.colors.background, Color::Rgb(0, 0, 0));
+
+    let missing = dir.join("missing.toml");
+    let config = Config::load_or(missing.to_str(), default).unwrap();
+    assert_eq!(config.warnings.len(), 1);
+}
+
+#[test]
+fn parses_color_syntaxes() {
+    use crate::config::parse_color;
+
+    assert_eq!(parse_color("#50fa7b"), Ok(Color::Rgb(0x50, 0xfa, 0x7b)));
+    assert_eq!(parse_color("50FA7B"), Ok(Color::Rgb(0x50, 0xfa, 0x7b)));
+    assert_eq!(parse_color("#f0a"), Ok(Color::Rgb(0xff, 0x00, 0xaa)));
+    assert_eq!(parse_color("208"), Ok(Color::Indexed(208)));
+    assert_eq!(parse_color("LightBlue"), Ok(Color::LightBlue));
+    assert_eq!(parse_color("light_blue"), Ok(Color::LightBlue));
+    assert_eq!(parse_color("dark-grey"), Ok(Color::DarkGray));
+    assert_eq!(parse_color("reset"), Ok(Color::Reset));
+    assert!(parse_color("#12").is_err());
+    assert!(parse_color("#ggg").is_err());
+    assert!(parse_color("256").is_err());
+    assert!(parse_color("blurple").is_err());
+
+    let config = Config::parse("[colors]\nitems = 33\nconnected = \"green\"").unwrap();
+    assert_eq!(config.colors.items, Color::Indexed(33));
+    assert_eq!(config.colors.connected, Color::Green);
+    assert!(Config::parse("[colors]\nitems = 300").is_err());
+}
+
+#[test]
+fn themes_can_be_overridden() {
+    use crate::config::Theme;
+
+    let config = Config::parse("theme = \"nord\"\n[colors]\nitems = \"red\"").unwrap();
+    let nord = Theme::Nord.palette();
+    assert_eq!(config.colors.items, Color::Red);
+    assert_eq!(config.colors.connected, nord.connected);
+    assert_ne!(nord.connected, Theme::Gruvbox.palette().connected);
+
+    assert!(Config::parse("theme = \"vaporwave\"").is_err());
+}
+
+#[test]
+fn app_reloads_config_in_place() {
+    use std::time::Duration;
+
+    use crossterm::event::KeyCode;
+
+    use super::{press, screen};
+    use crate::{backend::FakeBackend, config::ConfigWatcher, data::Location};
+
+    let dir = super::temp_dir("reload");
+    let path = dir.join("config.toml");
+    std::fs::write(&path, "[colors]\nitems = \"red\"\n").unwrap();
+
+    let backend = FakeBackend::default();
+    let mut app = super::app_with(&backend, Config::load(path.to_str()).unwrap())
+        .with_config_watcher(ConfigWatcher::new(path.to_str(), Duration::ZERO));
+
+    // Highlight Italy, then break the file.
+    press(&mut app, &[KeyCode::Char('j')]);
+    std::fs::write(&path, "[colors]\nitems = \"#1234\"\n").unwrap();
+    app.tick().unwrap();
+    assert!(screen(&mut app).contains("Config not reloaded"));
+
+    // Fixing it clears the error and applies new bindings, keeping the selection.
+    std::fs::write(&path, "[keys]\n\"x\" = \"select\"\n").unwrap();
+    app.tick().unwrap();
+    assert!(!screen(&mut app).contains("Config not reloaded"));
+
+    press(&mut app, &[KeyCode::Char('x'), KeyCode::Char('x')]);
+    super::wait_for(&mut app);
+    assert_eq!(
+        backend.connected_to(),
+        Some(Location::City("Milan".to_string()))
+    );
+}
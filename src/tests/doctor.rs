@@ -0,0 +1,41 @@
+use crate::{
+    backend::FakeBackend,
+    doctor::{self, Verdict},
+};
+
+fn verdict(checks: &[doctor::Check], name: &str) -> Verdict {
+    checks.iter().find(|c| c.name == name).unwrap().verdict
+}
+
+#[test]
+fn checks_login_and_config() {
+    let dir = super::temp_dir("doctor");
+    let path = dir.join("config.toml");
+    let backend = FakeBackend::default();
+
+    std::fs::write(&path, "theme = \"nord\"\n").unwrap();
+    let checks = doctor::run(&backend, path.to_str());
+    assert!(doctor::passed(&checks));
+    assert_eq!(verdict(&checks, "Config"), Verdict::Pass);
+
+    // Neither unknown keys nor broken values keep the interface from starting.
+    std::fs::write(&path, "colour = 1\n").unwrap();
+    let checks = doctor::run(&backend, path.to_str());
+    assert!(doctor::passed(&checks));
+    assert_eq!(verdict(&checks, "Config"), Verdict::Warn);
+
+    std::fs::write(&path, "[colors]\nitems = \"#12\"\n").unwrap();
+    let checks = doctor::run(&backend, path.to_str());
+    assert!(doctor::passed(&checks));
+    assert_eq!(verdict(&checks, "Config"), Verdict::Warn);
+
+    backend.set_logged_in(false);
+    let checks = doctor::run(&backend, path.to_str());
+    assert_eq!(verdict(&checks, "Login"), Verdict::Fail);
+    assert!(checks
+        .iter()
+        .find(|c| c.name == "Login")
+        .unwrap()
+        .remedy
+        .is_some());
+}
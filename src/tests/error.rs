@@ -0,0 +1,52 @@
+use crate::error::NordVpnError;
+
+#[test]
+fn recognises_nordvpn_failures() {
+    let cases = [
+        (
+            "Whoops! Permission denied accessing /run/nordvpn/nordvpnd.sock.",
+            NordVpnError::PermissionDenied,
+        ),
+        (
+            "Whoops! /run/nordvpn/nordvpnd.sock not found.",
+            NordVpnError::DaemonNotRunning,
+        ),
+        ("You are not logged in.", NordVpnError::NotLoggedIn),
+        (
+            "The specified server is not available at the moment or does not support your connection settings.",
+            NordVpnError::UnknownLocation,
+        ),
+        ("Whoops! Connection timed out.", NordVpnError::Timeout),
+        (
+            "Please check your internet connection and try again.",
+            NordVpnError::NoInternet,
+        ),
+    ];
+    for (output, expected) in cases {
+        assert_eq!(NordVpnError::detect(output), Some(expected), "{}", output);
+    }
+    assert_eq!(
+        NordVpnError::detect("You are not connected to NordVPN."),
+        None
+    );
+}
+
+#[test]
+fn every_failure_has_its_own_exit_code() {
+    let kinds = [
+        NordVpnError::NotInstalled,
+        NordVpnError::DaemonNotRunning,
+        NordVpnError::PermissionDenied,
+        NordVpnError::NotLoggedIn,
+        NordVpnError::UnknownLocation,
+        NordVpnError::Timeout,
+        NordVpnError::NoInternet,
+    ];
+    let mut codes: Vec<i32> = kinds.iter().map(|kind| kind.exit_code()).collect();
+    codes.sort();
+    codes.dedup();
+    assert_eq!(codes.len(), kinds.len());
+    // 0-4 belong to success, generic failures, usage errors, `status` and
+    // unrecognised rejections.
+    assert!(codes.iter().all(|&code| code > 4));
+}
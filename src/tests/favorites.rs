@@ -0,0 +1,99 @@
+use crossterm::event::KeyCode;
+
+use crate::{
+    backend::FakeBackend,
+    data::Location,
+    favorites::{Favorite, Favorites},
+};
+
+#[test]
+fn favorites_round_trip_through_file() {
+    let dir = super::temp_dir("favorites-round-trip");
+    let path = dir.join("favorites.json");
+
+    let mut favorites = Favorites::load(path.clone()).unwrap();
+    assert!(favorites.is_empty());
+
+    let berlin = Favorite {
+        country: "Germany".to_string(),
+        city: Some("Berlin".to_string()),
+    };
+    favorites.toggle(berlin.clone()).unwrap();
+    favorites
+        .toggle(Favorite {
+            country: "Japan".to_string(),
+            city: None,
+        })
+        .unwrap();
+
+    let reloaded = Favorites::load(path.clone()).unwrap();
+    assert_eq!(reloaded.items(), favorites.items());
+    assert_eq!(
+        reloaded.items()[1].location(),
+        Location::Country("Japan".to_string())
+    );
+
+    favorites.toggle(berlin.clone()).unwrap();
+    assert!(!Favorites::load(path.clone()).unwrap().contains(&berlin));
+}
+
+#[test]
+fn corrupt_favorites_are_backed_up_before_saving() {
+    let dir = super::temp_dir("favorites-corrupt");
+    let path = dir.join("favorites.json");
+    let backup = Favorites::backup_path(&path);
+    std::fs::write(&path, "[{\"country\": \"Ger").unwrap();
+
+    let mut favorites = Favorites::load(path.clone()).unwrap();
+    assert!(favorites.is_empty());
+    assert!(favorites.warning().unwrap().contains("favorites.json.bak"));
+    assert!(!backup.exists());
+
+    let italy = Favorite {
+        country: "Italy".to_string(),
+        city: None,
+    };
+    favorites.toggle(italy.clone()).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&backup).unwrap(),
+        "[{\"country\": \"Ger"
+    );
+    assert_eq!(Favorites::load(path.clone()).unwrap().items(), &[italy]);
+
+    // Later saves leave the backup alone.
+    favorites
+        .toggle(Favorite {
+            country: "Japan".to_string(),
+            city: None,
+        })
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&backup).unwrap(),
+        "[{\"country\": \"Ger"
+    );
+}
+
+#[test]
+fn app_stars_and_connects_to_favorites() {
+    let backend = FakeBackend::default();
+    let mut app = super::app(&backend).with_favorites(Favorites::default());
+
+    // Star Germany, then Frankfurt inside it.
+    super::press(
+        &mut app,
+        &[
+            KeyCode::Char('f'),
+            KeyCode::Enter,
+            KeyCode::Char('j'),
+            KeyCode::Char('f'),
+            KeyCode::Char('F'),
+            KeyCode::Char('j'),
+            KeyCode::Enter,
+        ],
+    );
+    super::wait_for(&mut app);
+    assert_eq!(
+        backend.connected_to(),
+        Some(Location::City("Frankfurt".to_string()))
+    );
+}
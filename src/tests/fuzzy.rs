@@ -0,0 +1,87 @@
+use crossterm::event::KeyCode;
+
+use super::{press, typed};
+use crate::{
+    backend::FakeBackend,
+    fuzzy::{self, fuzzy_match},
+};
+
+#[test]
+fn matches_subsequences_case_insensitively() {
+    let m = fuzzy_match("unkgdm", "United_Kingdom").unwrap();
+    assert_eq!(m.positions, vec![0, 1, 7, 10, 11, 13]);
+    assert!(fuzzy_match("nwyrk", "New_York").is_some());
+    assert!(fuzzy_match("new york", "New_York").is_some());
+    assert!(fuzzy_match("yorkn", "New_York").is_none());
+    assert_eq!(
+        fuzzy_match("", "Italy").unwrap().positions,
+        Vec::<usize>::new()
+    );
+}
+
+#[test]
+fn ranks_tighter_and_word_start_matches_first() {
+    let items: Vec<String> = ["Bulgaria", "United_Kingdom", "Uruguay", "Ukraine"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+    let ranked: Vec<&str> = fuzzy::filter("uk", &items)
+        .into_iter()
+        .map(|(i, _)| items[i].as_str())
+        .collect();
+    assert_eq!(ranked, vec!["Ukraine", "United_Kingdom"]);
+
+    let ranked: Vec<&str> = fuzzy::filter("ug", &items)
+        .into_iter()
+        .map(|(i, _)| items[i].as_str())
+        .collect();
+    assert_eq!(ranked, vec!["Uruguay", "United_Kingdom", "Bulgaria"]);
+}
+
+#[test]
+fn app_searches_countries_fuzzily() {
+    let mut app = super::app(&FakeBackend::default());
+
+    press(&mut app, &typed("/unkgdm"));
+    press(&mut app, &[KeyCode::Enter]);
+
+    let screen = super::screen(&mut app);
+    assert!(screen.contains("London"), "{}", screen);
+}
+
+#[test]
+fn search_keeps_master_lists_and_selection() {
+    let backend = FakeBackend::default();
+    let mut app = super::app(&backend);
+
+    // Narrow down to Japan, then backspace the whole query away: Japan stays
+    // highlighted and its cities are the ones that open.
+    press(&mut app, &typed("/jap"));
+    press(&mut app, &[KeyCode::Backspace; 3]);
+    press(&mut app, &[KeyCode::Enter]);
+    // Then pick Tokyo out of a filtered city list.
+    press(&mut app, &typed("/tk"));
+    press(&mut app, &[KeyCode::Enter, KeyCode::Enter]);
+    super::wait_for(&mut app);
+    assert_eq!(
+        backend.connected_to(),
+        Some(crate::data::Location::City("Tokyo".to_string()))
+    );
+}
+
+#[test]
+fn search_only_opens_on_country_and_city_lists() {
+    use crate::{backend::Backend, settings::Toggle};
+
+    let backend = FakeBackend::default();
+    let mut app = super::app(&backend);
+
+    // The space toggles the second setting instead of going into a query.
+    press(&mut app, &typed("sj/ "));
+    super::wait_for(&mut app);
+    assert_eq!(
+        backend.settings().unwrap().get(Toggle::AutoConnect),
+        Some(true)
+    );
+}
@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use crossterm::event::KeyCode;
+
+use super::press;
+use crate::{
+    backend::FakeBackend,
+    data::Location,
+    history::{Entry, History, Outcome},
+};
+
+#[test]
+fn history_round_trip_through_file() {
+    let dir = super::temp_dir("history-round-trip");
+    let path = dir.join("history.jsonl");
+
+    let mut history = History::load(path.clone()).unwrap();
+    assert_eq!(history.recent().count(), 0);
+
+    let berlin = Location::City("Berlin".to_string());
+    let japan = Location::Country("Japan".to_string());
+    history
+        .record(Entry::new(
+            berlin.clone(),
+            Outcome::Connected,
+            Duration::from_secs(3),
+        ))
+        .unwrap();
+    history
+        .record(Entry::new(
+            japan.clone(),
+            Outcome::Failed,
+            Duration::from_secs(1),
+        ))
+        .unwrap();
+
+    // Garbage lines are skipped rather than failing the whole file.
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| std::io::Write::write_all(&mut file, b"not json\n"))
+        .unwrap();
+
+    let reloaded = History::load(path.clone()).unwrap();
+    let recent: Vec<&Entry> = reloaded.recent().collect();
+    assert_eq!(recent.len(), 2);
+    assert_eq!(recent[0].location, japan);
+    assert_eq!(recent[0].outcome, Outcome::Failed);
+    assert_eq!(recent[1].duration_ms, 3000);
+    assert_eq!(reloaded.last_location(), Some(&berlin));
+}
+
+#[test]
+fn app_records_connects_and_reconnects() {
+    let backend = FakeBackend::default();
+    let mut app = super::app(&backend).with_history(History::default());
+
+    // Nothing to reconnect to yet.
+    press(&mut app, &[KeyCode::Char('r')]);
+    assert!(!app.is_busy());
+
+    // Germany -> Berlin.
+    press(&mut app, &[KeyCode::Enter, KeyCode::Enter]);
+    super::wait_for(&mut app);
+
+    press(&mut app, &[KeyCode::Char('D')]);
+    super::wait_for(&mut app);
+    assert_eq!(backend.connected_to(), None);
+
+    press(&mut app, &[KeyCode::Char('r')]);
+    super::wait_for(&mut app);
+    assert_eq!(
+        backend.connected_to(),
+        Some(Location::City("Berlin".to_string()))
+    );
+}
+
+#[test]
+fn unwritable_history_does_not_fail_the_connection() {
+    use crate::{cli::Command, commands::Runner};
+
+    let dir = super::temp_dir("history-unwritable");
+    let path = dir.join("history.jsonl");
+    let history = History::load(path.clone()).unwrap();
+    // Appending to a directory fails like a full or read-only disk would.
+    std::fs::create_dir_all(&path).unwrap();
+
+    let backend = FakeBackend::default();
+    let mut runner = Runner::new(std::sync::Arc::new(backend.clone()), false).with_history(history);
+    let connect = Command::Connect {
+        country: "Italy".to_string(),
+        city: None,
+    };
+    assert_eq!(runner.run(connect, &mut Vec::new()).unwrap(), 0);
+    assert_eq!(
+        backend.connected_to(),
+        Some(Location::Country("Italy".to_string()))
+    );
+}
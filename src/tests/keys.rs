@@ -0,0 +1,148 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{
+    backend::FakeBackend,
+    data::Location,
+    keys::{Action, KeyChord, Keymap},
+};
+
+fn press(keymap: &Keymap, pending: &mut Vec<KeyChord>, code: KeyCode) -> Option<Action> {
+    keymap.resolve(pending, KeyEvent::from(code))
+}
+
+#[test]
+fn parses_key_chords() {
+    assert_eq!(KeyChord::parse("H"), KeyChord::parse("shift-h"));
+    assert_eq!(
+        KeyChord::parse("H").unwrap(),
+        KeyChord::from(KeyEvent::new(KeyCode::Char('h'), KeyModifiers::SHIFT))
+    );
+    assert_eq!(
+        KeyChord::parse("Ctrl-c").unwrap(),
+        KeyChord::from(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL))
+    );
+    assert_eq!(KeyChord::parse("ctrl--").unwrap().to_string(), "Ctrl--");
+    assert_eq!(KeyChord::parse("space").unwrap().to_string(), "Space");
+    assert_eq!(KeyChord::parse("f5").unwrap().to_string(), "F5");
+    assert!(KeyChord::parse("f13").is_err());
+    assert!(KeyChord::parse("hyper").is_err());
+}
+
+#[test]
+fn default_keymap_resolves_sequences() {
+    let keymap = Keymap::default();
+    let mut pending = Vec::new();
+
+    assert_eq!(press(&keymap, &mut pending, KeyCode::Char('g')), None);
+    assert_eq!(
+        press(&keymap, &mut pending, KeyCode::Char('g')),
+        Some(Action::Top)
+    );
+    assert!(pending.is_empty());
+
+    // A stray `g` does not swallow the next key.
+    assert_eq!(press(&keymap, &mut pending, KeyCode::Char('g')), None);
+    assert_eq!(
+        press(&keymap, &mut pending, KeyCode::Char('j')),
+        Some(Action::Down)
+    );
+    assert_eq!(press(&keymap, &mut pending, KeyCode::Char('z')), None);
+    assert!(pending.is_empty());
+
+    assert_eq!(keymap.describe(Action::Down).as_deref(), Some("J | Down"));
+    assert_eq!(keymap.describe(Action::Top).as_deref(), Some("G G"));
+    assert_eq!(keymap.describe(Action::History).as_deref(), Some("Shift-H"));
+}
+
+#[test]
+fn keys_table_overrides_defaults() {
+    let keymap: Keymap = toml::from_str(
+        r#"
+        "ctrl-c" = "quit"
+        "q" = "none"
+        "D" = "quick-connect"
+        "#,
+    )
+    .unwrap();
+
+    let mut pending = Vec::new();
+    assert_eq!(press(&keymap, &mut pending, KeyCode::Char('q')), None);
+    assert_eq!(keymap.describe(Action::Quit).as_deref(), Some("Ctrl-C"));
+    assert_eq!(keymap.describe(Action::Disconnect), None);
+    assert_eq!(
+        keymap.describe(Action::QuickConnect).as_deref(),
+        Some("Shift-C | Shift-D")
+    );
+
+    assert!(toml::from_str::<Keymap>(r#""x" = "explode""#).is_err());
+    assert!(toml::from_str::<Keymap>(r#""hyper-x" = "quit""#).is_err());
+}
+
+#[test]
+fn app_uses_configured_keys() {
+    let backend = FakeBackend::default();
+    let config = crate::config::Config {
+        keys: toml::from_str(r#""x" = "quick-connect""#).unwrap(),
+        ..Default::default()
+    };
+    let mut app = super::app_with(&backend, config);
+
+    super::press(&mut app, &[KeyCode::Char('x')]);
+    super::wait_for(&mut app);
+    assert_eq!(backend.connected_to(), Some(Location::Recommended));
+}
+
+#[test]
+fn busy_header_shows_the_cancel_binding() {
+    let backend = FakeBackend::default().with_delay(std::time::Duration::from_secs(60));
+    let config = crate::config::Config {
+        keys: toml::from_str(
+            r#"
+            "esc" = "none"
+            "ctrl-c" = "cancel"
+            "#,
+        )
+        .unwrap(),
+        ..Default::default()
+    };
+    let mut app = super::app_with(&backend, config);
+    super::press(&mut app, &[KeyCode::Char('C')]);
+
+    let screen = super::screen(&mut app);
+    assert!(screen.contains("<Ctrl-C> to cancel"));
+    assert!(!screen.contains("<Esc> to cancel"));
+
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL))
+        .unwrap();
+    super::wait_for(&mut app);
+}
+
+#[test]
+fn instruction_bar_fits_and_leads_with_help() {
+    let backend = FakeBackend::default();
+    let mut app = super::app(&backend);
+
+    let bar = instruction_bar(&mut app);
+    assert!(bar.find("Help <?>").unwrap() < bar.find("Select").unwrap());
+    assert!(bar.contains("Connect country <C>"));
+    assert!(bar.contains("Quit <Q>"));
+    assert!(!bar.contains("History"));
+
+    super::press(&mut app, &[KeyCode::Char('s')]);
+    let bar = instruction_bar(&mut app);
+    assert!(bar.contains("Toggle"));
+    assert!(bar.contains("Quit <Q>"));
+    assert!(!bar.contains("Connect country"));
+}
+
+/// The screen row holding the normal-mode instructions.
+fn instruction_bar(app: &mut crate::app::App) -> String {
+    let buffer = super::draw(app);
+    let width = buffer.area.width as usize;
+    let cells: Vec<&str> = buffer.content().iter().map(|c| c.symbol()).collect();
+    cells
+        .chunks(width)
+        .map(|row| row.concat())
+        .find(|row| row.contains("Normal |"))
+        .unwrap()
+}
@@ -0,0 +1,31 @@
+use log::LevelFilter;
+
+use crate::logging;
+
+#[test]
+fn verbosity_raises_the_level() {
+    assert_eq!(logging::level(0), LevelFilter::Warn);
+    assert_eq!(logging::level(1), LevelFilter::Info);
+    assert_eq!(logging::level(2), LevelFilter::Debug);
+    assert_eq!(logging::level(3), LevelFilter::Trace);
+    assert_eq!(logging::level(9), LevelFilter::Trace);
+}
+
+// The only test installing the global logger: it can be set once per process.
+#[test]
+fn writes_records_and_rotates_large_logs() {
+    let dir = super::temp_dir("log");
+    let path = dir.join("nordvpn-tui.log");
+    std::fs::write(&path, "x".repeat(2 * 1024 * 1024)).unwrap();
+
+    logging::init(path.clone(), 1).unwrap();
+    log::info!("connected to Berlin");
+    log::debug!("too verbose for -v");
+    log::logger().flush();
+
+    let log = std::fs::read_to_string(&path).unwrap();
+    assert!(log.starts_with("--- nordvpn-tui"));
+    assert!(log.contains("INFO  tests::logging: connected to Berlin"));
+    assert!(!log.contains("too verbose"));
+    assert!(dir.join("nordvpn-tui.log.old").exists());
+}
@@ -0,0 +1,355 @@
+mod colors;
+mod commands;
+mod config;
+mod doctor;
+mod error;
+mod favorites;
+mod fuzzy;
+mod history;
+mod keys;
+mod logging;
+mod settings;
+mod shutdown;
+mod status;
+
+use std::{
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{backend::TestBackend, buffer::Buffer, Terminal};
+
+use crate::{app::App, backend::FakeBackend, config::Config, data::Location};
+
+#[test]
+fn load_config() {
+    dotenv::dotenv().ok();
+    // let cli = <crate::cli::Cli as clap::Parser>::parse();
+    let config = crate::config::Config::load(Some("config.toml")).unwrap();
+    println!("{:?}", config);
+}
+
+/// An app driving `backend`, with the default config.
+fn app(backend: &FakeBackend) -> App {
+    app_with(backend, Config::default())
+}
+
+fn app_with(backend: &FakeBackend, config: Config) -> App {
+    App::new(config, Arc::new(backend.clone())).unwrap()
+}
+
+fn press(app: &mut App, codes: &[KeyCode]) {
+    for code in codes {
+        app.handle_key_event(KeyEvent::from(*code)).unwrap();
+    }
+}
+
+/// `text` as key presses, for the search and server prompts.
+fn typed(text: &str) -> Vec<KeyCode> {
+    text.chars().map(KeyCode::Char).collect()
+}
+
+/// Draws `app` on a 120x20 terminal.
+fn draw(app: &mut App) -> Buffer {
+    let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+    terminal.draw(|f| app.draw(f)).unwrap();
+    terminal.backend().buffer().clone()
+}
+
+/// The text `draw` puts on the screen, row after row.
+fn screen(app: &mut App) -> String {
+    draw(app).content().iter().map(|c| c.symbol()).collect()
+}
+
+/// A directory of its own for a test, removed again when dropped.
+struct TempDir(PathBuf);
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// An empty `nordvpn-tui-<name>-<pid>` directory in the system temp dir.
+fn temp_dir(name: &str) -> TempDir {
+    let path = std::env::temp_dir().join(format!("nordvpn-tui-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(&path).unwrap();
+    TempDir(path)
+}
+
+fn wait_for(app: &mut App) {
+    let start = std::time::Instant::now();
+    while app.is_busy() {
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        let _ = app.tick();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn fake_backend_connects_to_known_locations() {
+    use crate::backend::{Backend, CancelToken};
+
+    let backend = FakeBackend::default();
+    assert!(backend
+        .countries()
+        .unwrap()
+        .contains(&"Germany".to_string()));
+    assert_eq!(backend.cities("Italy").unwrap(), vec!["Milan", "Rome"]);
+
+    let cancel = CancelToken::default();
+    assert!(!backend
+        .connect(&Location::City("Atlantis".to_string()), &cancel)
+        .unwrap()
+        .status
+        .success());
+    assert!(backend
+        .connect(&Location::City("Berlin".to_string()), &cancel)
+        .unwrap()
+        .status
+        .success());
+    assert_eq!(
+        backend.connected_to(),
+        Some(Location::City("Berlin".to_string()))
+    );
+    assert!(backend.status().unwrap().is_connected());
+
+    assert!(backend.disconnect(&cancel).unwrap().status.success());
+    assert_eq!(backend.connected_to(), None);
+}
+
+#[test]
+fn app_connects_through_backend() {
+    let backend = FakeBackend::new(&[("Germany", &["Berlin", "Frankfurt"])]);
+    let mut app = app(&backend);
+
+    press(
+        &mut app,
+        &[KeyCode::Enter, KeyCode::Char('j'), KeyCode::Enter],
+    );
+    wait_for(&mut app);
+    assert_eq!(
+        backend.connected_to(),
+        Some(Location::City("Frankfurt".to_string()))
+    );
+}
+
+#[test]
+fn app_connects_to_whole_countries() {
+    let backend = FakeBackend::new(&[("Germany", &["Berlin"]), ("Italy", &["Rome"])]);
+    let mut app = app(&backend);
+
+    // "Any city" sits above the first city.
+    press(
+        &mut app,
+        &[KeyCode::Enter, KeyCode::Char('k'), KeyCode::Enter],
+    );
+    wait_for(&mut app);
+    assert_eq!(
+        backend.connected_to(),
+        Some(Location::Country("Germany".to_string()))
+    );
+
+    // Straight from the country list.
+    press(&mut app, &typed("hhjc"));
+    wait_for(&mut app);
+    assert_eq!(
+        backend.connected_to(),
+        Some(Location::Country("Italy".to_string()))
+    );
+}
+
+#[test]
+fn app_cancels_in_flight_connect() {
+    let backend = FakeBackend::new(&[("Germany", &["Berlin"])])
+        .with_delay(std::time::Duration::from_secs(60));
+    let mut app = app(&backend);
+
+    press(&mut app, &[KeyCode::Enter, KeyCode::Enter]);
+    assert!(app.is_busy());
+
+    press(&mut app, &[KeyCode::Esc]);
+    wait_for(&mut app);
+    assert_eq!(backend.connected_to(), None);
+}
+
+#[test]
+fn app_notices_connections_made_elsewhere() {
+    use crate::backend::{Backend, CancelToken};
+
+    let backend = FakeBackend::default();
+    let mut app = app(&backend);
+    assert!(!app.status().is_connected());
+
+    backend
+        .connect(
+            &Location::City("Tokyo".to_string()),
+            &CancelToken::default(),
+        )
+        .unwrap();
+    app.refresh_status();
+
+    let start = std::time::Instant::now();
+    while !app.status().is_connected() {
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        app.tick().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    assert_eq!(app.status().city.as_deref(), Some("Tokyo"));
+}
+
+#[test]
+fn status_pane_shows_connection_details() {
+    use crate::backend::{Backend, CancelToken};
+
+    let backend = FakeBackend::default();
+    backend
+        .connect(
+            &Location::City("Tokyo".to_string()),
+            &CancelToken::default(),
+        )
+        .unwrap();
+
+    let screen = screen(&mut app(&backend));
+    assert!(screen.contains("Tokyo, Japan"));
+    assert!(screen.contains("ja42.nordvpn.com"));
+    assert!(screen.contains("00:00:00"));
+}
+
+#[test]
+fn app_connects_to_server_groups() {
+    let backend = FakeBackend::default();
+    let mut app = app(&backend);
+
+    // P2P is the fifth group, Germany the first country after "Any country".
+    press(&mut app, &typed("pjjjj"));
+    press(
+        &mut app,
+        &[KeyCode::Enter, KeyCode::Char('j'), KeyCode::Enter],
+    );
+    wait_for(&mut app);
+    assert_eq!(
+        backend.connected_to(),
+        Some(Location::Group {
+            group: "P2P".to_string(),
+            country: Some("Germany".to_string()),
+        })
+    );
+}
+
+#[test]
+fn location_builds_connect_arguments() {
+    assert_eq!(Location::City("Berlin".to_string()).args(), vec!["Berlin"]);
+    assert_eq!(
+        Location::Group {
+            group: "Double_VPN".to_string(),
+            country: None,
+        }
+        .args(),
+        vec!["--group", "Double_VPN"]
+    );
+    assert_eq!(
+        Location::Group {
+            group: "P2P".to_string(),
+            country: Some("Germany".to_string()),
+        }
+        .args(),
+        vec!["--group", "P2P", "Germany"]
+    );
+}
+
+#[test]
+fn parses_server_names() {
+    use crate::data::parse_server;
+
+    assert_eq!(parse_server("de1234"), Some("de1234".to_string()));
+    assert_eq!(
+        parse_server(" DE1234.nordvpn.com "),
+        Some("de1234".to_string())
+    );
+    assert_eq!(parse_server("us-ca10"), Some("us-ca10".to_string()));
+    assert_eq!(parse_server("de"), None);
+    assert_eq!(parse_server("1234"), None);
+    assert_eq!(parse_server("germany12"), None);
+    assert_eq!(parse_server("de12a"), None);
+    assert_eq!(parse_server("-de12"), None);
+}
+
+#[test]
+fn app_quick_connects_and_connects_to_servers() {
+    let backend = FakeBackend::default();
+    let mut app = app(&backend);
+
+    press(&mut app, &[KeyCode::Char('C')]);
+    wait_for(&mut app);
+    assert_eq!(backend.connected_to(), Some(Location::Recommended));
+
+    // A malformed name keeps the prompt open instead of connecting.
+    press(&mut app, &typed("ntokyo"));
+    press(&mut app, &[KeyCode::Enter]);
+    assert!(!app.is_busy());
+
+    press(&mut app, &[KeyCode::Backspace; 5]);
+    press(&mut app, &typed("JA12"));
+    press(&mut app, &[KeyCode::Enter]);
+    wait_for(&mut app);
+    assert_eq!(
+        backend.connected_to(),
+        Some(Location::Server("ja12".to_string()))
+    );
+}
+
+#[test]
+fn app_shows_failed_commands_in_a_dismissible_popup() {
+    let mut app = app(&FakeBackend::default());
+
+    press(&mut app, &typed("nzz12"));
+    press(&mut app, &[KeyCode::Enter]);
+    wait_for(&mut app);
+
+    // Recognised failures come with a remedy, and the output stays visible.
+    let (title, lines) = app.error().unwrap();
+    assert_eq!(title, "NordVPN does not know or cannot use that location");
+    assert!(lines[0].starts_with("Check the name"));
+    assert!(lines.iter().any(|l| l.contains("not available")));
+
+    // Other keys are swallowed until the popup is dismissed.
+    press(&mut app, &[KeyCode::Char('C')]);
+    assert!(!app.is_busy());
+    press(&mut app, &[KeyCode::Esc]);
+    assert!(app.error().is_none());
+}
+
+#[test]
+fn app_opens_the_checklist_until_checks_pass() {
+    let dir = temp_dir("checklist");
+    let config = dir.join("config.toml");
+    let config = config.to_str();
+    let backend = FakeBackend::default();
+    backend.set_logged_in(false);
+    let checks = crate::doctor::run(&backend, config);
+    let mut app = app(&backend)
+        .with_config_watcher(crate::config::ConfigWatcher::new(
+            config,
+            std::time::Duration::from_secs(60),
+        ))
+        .with_checks(checks);
+    assert!(screen(&mut app).contains("✗ Login"));
+
+    backend.set_logged_in(true);
+    press(&mut app, &[KeyCode::Enter]);
+    let screen = screen(&mut app);
+    assert!(screen.contains("✓ Login"));
+    assert!(screen.contains("All checks passed."));
+}
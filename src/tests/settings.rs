@@ -0,0 +1,138 @@
+use crate::settings::{Choice, Protocol, Settings, Technology, Toggle};
+
+#[test]
+fn parses_v3_17_nordlynx() {
+    let settings = Settings::parse(include_str!("fixtures/settings/v3.17_nordlynx.txt"));
+
+    assert_eq!(settings.technology, Some(Technology::NordLynx));
+    assert_eq!(settings.protocol, None);
+    assert_eq!(settings.get(Toggle::KillSwitch), Some(true));
+    assert_eq!(settings.get(Toggle::AutoConnect), Some(true));
+    assert_eq!(settings.get(Toggle::ThreatProtection), Some(false));
+    assert_eq!(settings.get(Toggle::LanDiscovery), Some(true));
+    assert_eq!(settings.get(Toggle::Meshnet), Some(false));
+    assert_eq!(settings.get(Toggle::PostQuantum), Some(false));
+    assert_eq!(settings.get(Toggle::Obfuscate), None);
+}
+
+#[test]
+fn parses_v3_12_openvpn_legacy_keys() {
+    let settings = Settings::parse(include_str!("fixtures/settings/v3.12_openvpn.txt"));
+
+    assert_eq!(settings.technology, Some(Technology::OpenVpn));
+    assert_eq!(settings.protocol, Some(Protocol::Tcp));
+    assert_eq!(settings.connection_type().as_deref(), Some("OpenVPN (TCP)"));
+    assert_eq!(settings.get(Toggle::ThreatProtection), Some(true));
+    assert_eq!(settings.get(Toggle::Obfuscate), Some(true));
+    assert_eq!(settings.get(Toggle::Notify), Some(true));
+    assert_eq!(settings.get(Toggle::Meshnet), None);
+}
+
+#[test]
+fn app_toggles_settings_with_space() {
+    use crossterm::event::KeyCode;
+
+    use super::press;
+    use crate::backend::{Backend, FakeBackend};
+
+    let backend = FakeBackend::default();
+    let mut app = super::app(&backend);
+
+    press(&mut app, &[KeyCode::Char('s'), KeyCode::Char(' ')]);
+    super::wait_for(&mut app);
+    assert_eq!(
+        backend.settings().unwrap().get(Toggle::KillSwitch),
+        Some(true)
+    );
+
+    press(&mut app, &[KeyCode::Char('G'), KeyCode::Char(' ')]);
+    super::wait_for(&mut app);
+    assert_eq!(
+        backend.settings().unwrap().get(Toggle::PostQuantum),
+        Some(true)
+    );
+}
+
+#[test]
+fn technology_choices_respect_compatibility() {
+    let nordlynx = Settings::parse(include_str!("fixtures/settings/v3.17_nordlynx.txt"));
+    assert!(Choice::Technology(Technology::NordLynx).is_active(&nordlynx));
+    assert!(Choice::Technology(Technology::OpenVpn)
+        .conflict(&nordlynx)
+        .is_none());
+    assert!(Choice::Protocol(Protocol::Tcp)
+        .conflict(&nordlynx)
+        .is_some());
+
+    // Obfuscation is on, so leaving OpenVPN is not allowed.
+    let obfuscated = Settings::parse(include_str!("fixtures/settings/v3.12_openvpn.txt"));
+    assert!(Choice::Technology(Technology::NordLynx)
+        .conflict(&obfuscated)
+        .is_some());
+    assert!(Choice::Protocol(Protocol::Udp)
+        .conflict(&obfuscated)
+        .is_none());
+
+    let mut post_quantum = nordlynx.clone();
+    post_quantum.toggles.insert(Toggle::PostQuantum, true);
+    assert!(Choice::Technology(Technology::OpenVpn)
+        .conflict(&post_quantum)
+        .is_some());
+}
+
+#[test]
+fn app_switches_technology_and_protocol() {
+    use crossterm::event::KeyCode;
+
+    use super::press;
+    use crate::backend::{Backend, FakeBackend};
+
+    let backend = FakeBackend::default();
+    let mut app = super::app(&backend);
+
+    // Protocol is greyed out on NordLynx.
+    press(
+        &mut app,
+        &[KeyCode::Char('t'), KeyCode::Char('G'), KeyCode::Enter],
+    );
+    assert!(!app.is_busy());
+
+    press(
+        &mut app,
+        &[
+            KeyCode::Char('g'),
+            KeyCode::Char('g'),
+            KeyCode::Char('j'),
+            KeyCode::Enter,
+        ],
+    );
+    super::wait_for(&mut app);
+    assert_eq!(
+        backend.settings().unwrap().technology,
+        Some(Technology::OpenVpn)
+    );
+
+    press(&mut app, &[KeyCode::Char('G'), KeyCode::Enter]);
+    super::wait_for(&mut app);
+    assert_eq!(backend.settings().unwrap().protocol, Some(Protocol::Tcp));
+}
+
+#[test]
+fn settings_changes_do_not_offer_cancel() {
+    use crossterm::event::KeyCode;
+
+    use crate::backend::{Backend, FakeBackend};
+
+    let backend = FakeBackend::default();
+    let mut app = super::app(&backend);
+    super::press(&mut app, &[KeyCode::Char('s'), KeyCode::Char(' ')]);
+    assert!(app.is_busy());
+    assert!(!super::screen(&mut app).to_lowercase().contains("cancel"));
+
+    super::press(&mut app, &[KeyCode::Esc]);
+    super::wait_for(&mut app);
+    assert_eq!(
+        backend.settings().unwrap().get(Toggle::KillSwitch),
+        Some(true)
+    );
+}
@@ -0,0 +1,22 @@
+use std::time::{Duration, Instant};
+
+use signal_hook::consts::SIGHUP;
+
+use crate::shutdown;
+
+// The only test raising a signal: a second one would end the test run.
+#[test]
+fn signals_ask_the_main_loop_to_quit() {
+    shutdown::handle_signals().unwrap();
+    assert_eq!(shutdown::received(), None);
+
+    signal_hook::low_level::raise(SIGHUP).unwrap();
+    let start = Instant::now();
+    while shutdown::received().is_none() {
+        assert!(start.elapsed() < Duration::from_secs(5));
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(shutdown::received(), Some(SIGHUP));
+    assert_eq!(shutdown::exit_code(SIGHUP), 129);
+    assert_eq!(shutdown::name(SIGHUP), "hung up");
+}
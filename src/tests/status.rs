@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use crate::status::{State, Status, Transfer};
+
+#[test]
+fn parses_v3_16_connected() {
+    let status = Status::parse(include_str!("fixtures/status/v3.16_connected.txt"));
+
+    assert_eq!(status.state, State::Connected);
+    assert_eq!(status.hostname.as_deref(), Some("de1043.nordvpn.com"));
+    assert_eq!(status.ip.as_deref(), Some("185.130.184.76"));
+    assert_eq!(status.country.as_deref(), Some("Germany"));
+    assert_eq!(status.city.as_deref(), Some("Frankfurt"));
+    assert_eq!(status.technology.as_deref(), Some("NORDLYNX"));
+    assert_eq!(status.protocol.as_deref(), Some("UDP"));
+    assert_eq!(
+        status.transfer,
+        Some(Transfer {
+            received: 1_604_321,
+            sent: 427_274,
+        })
+    );
+    assert_eq!(status.uptime, Some(Duration::from_secs(3852)));
+}
+
+#[test]
+fn parses_v3_17_connected_with_notice() {
+    let status = Status::parse(include_str!("fixtures/status/v3.17_connected.txt"));
+
+    assert_eq!(status.state, State::Connected);
+    assert_eq!(status.server.as_deref(), Some("United States #9437"));
+    assert_eq!(status.hostname.as_deref(), Some("us9437.nordvpn.com"));
+    assert_eq!(status.city.as_deref(), Some("New York"));
+    assert_eq!(status.technology.as_deref(), Some("OPENVPN"));
+    assert_eq!(status.protocol.as_deref(), Some("TCP"));
+    assert_eq!(
+        status.uptime,
+        Some(Duration::from_secs(2 * 86_400 + 3 * 3_600 + 60 + 9))
+    );
+}
+
+#[test]
+fn parses_v3_7_legacy_keys() {
+    let status = Status::parse(include_str!("fixtures/status/v3.7_connected.txt"));
+
+    assert_eq!(status.state, State::Connected);
+    assert_eq!(status.hostname.as_deref(), Some("se512.nordvpn.com"));
+    assert_eq!(status.ip.as_deref(), Some("196.196.203.12"));
+    assert_eq!(
+        status.transfer,
+        Some(Transfer {
+            received: 512,
+            sent: 2048,
+        })
+    );
+    assert_eq!(status.uptime, Some(Duration::from_secs(45)));
+}
+
+#[test]
+fn parses_disconnected() {
+    let status = Status::parse(include_str!("fixtures/status/v3.16_disconnected.txt"));
+
+    assert_eq!(status, Status::default());
+    assert!(!status.is_connected());
+    assert_eq!(status.summary(), "Disconnected");
+}
+
+#[test]
+fn parses_connecting_through_spinner() {
+    let status = Status::parse(include_str!("fixtures/status/v3.18_connecting.txt"));
+
+    assert_eq!(status.state, State::Connecting);
+    assert_eq!(status.hostname.as_deref(), Some("jp512.nordvpn.com"));
+    assert_eq!(status.ip, None);
+    assert_eq!(
+        status.summary(),
+        "Connecting | Tokyo, Japan | jp512.nordvpn.com"
+    );
+}
+
+#[test]
+fn renders_templates() {
+    let status = Status {
+        state: State::Connected,
+        country: Some("Germany".to_string()),
+        city: Some("Berlin".to_string()),
+        ip: Some("185.1.2.3".to_string()),
+        uptime: Some(Duration::from_secs(65)),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        status.render("{country} {city} {ip}"),
+        "Germany Berlin 185.1.2.3"
+    );
+    assert_eq!(status.render("{state} ({uptime})"), "Connected (00:01:05)");
+    assert_eq!(status.render("{{{city}}} {technology}"), "{Berlin}");
+    assert_eq!(status.render("{bogus} {city"), "{bogus} {city");
+}
+
+#[test]
+fn rows_skip_unknown_fields() {
+    let status = Status {
+        state: State::Connected,
+        server: Some("Germany #1".to_string()),
+        technology: Some("NORDLYNX".to_string()),
+        uptime: Some(Duration::from_secs(60)),
+        ..Default::default()
+    };
+    assert_eq!(
+        status.rows(Duration::from_secs(5)),
+        vec![
+            ("State", "Connected".to_string()),
+            ("Server", "Germany #1".to_string()),
+            ("Technology", "NORDLYNX".to_string()),
+            ("Uptime", "00:01:05".to_string()),
+        ]
+    );
+}